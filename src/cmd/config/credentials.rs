@@ -1,10 +1,56 @@
-use crate::{cmd::errors::AppError, env_vars};
+use crate::{
+    cmd::{
+        errors::AppError,
+        models::{CredentialOptions, CredentialStore},
+        SERVICE_NAME,
+    },
+    env_vars,
+};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use dco3::{client::DracoonClient, OAuth2Flow};
 use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use super::{
+    file_store::EncryptedFileStore,
+    tenants::{register_tenant, unregister_tenant},
+};
+
+// refresh token as persisted in the credential store
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct StoredRefreshToken {
+    pub refresh_token: String,
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
 
 pub trait HandleCredentials {
     fn set_dracoon_env(&self, secret: &str) -> Result<(), AppError>;
     fn get_dracoon_env(&self) -> Result<String, AppError>;
     fn delete_dracoon_env(&self) -> Result<(), AppError>;
+
+    fn set_refresh_token(&self, refresh_token: &str) -> Result<(), AppError> {
+        let stored = StoredRefreshToken {
+            refresh_token: refresh_token.to_string(),
+            refreshed_at: Some(Utc::now()),
+        };
+        let secret =
+            serde_json::to_string(&stored).map_err(|_| AppError::CredentialStorageFailed)?;
+
+        self.set_dracoon_env(&secret)
+    }
+
+    fn get_refresh_token(&self) -> Result<StoredRefreshToken, AppError> {
+        let secret = self.get_dracoon_env()?;
+
+        // entries written by older versions only contain the plain refresh token
+        Ok(serde_json::from_str(&secret).unwrap_or(StoredRefreshToken {
+            refresh_token: secret,
+            refreshed_at: None,
+        }))
+    }
 }
 
 impl HandleCredentials for Entry {
@@ -38,3 +84,182 @@ pub fn get_client_credentials() -> (String, String) {
 
     (client_id, client_secret)
 }
+
+/// Stores the refresh token and remembers the DRACOON url for `logout --all`
+pub fn store_refresh_token(
+    entry: &dyn HandleCredentials,
+    base_url: &str,
+    refresh_token: &str,
+) -> Result<(), AppError> {
+    entry.set_refresh_token(refresh_token)?;
+    register_tenant(base_url);
+
+    Ok(())
+}
+
+/// Deletes the refresh token and removes the DRACOON url from the tenant index
+pub fn delete_refresh_token(entry: &dyn HandleCredentials, base_url: &str) -> Result<(), AppError> {
+    unregister_tenant(base_url);
+    entry.delete_dracoon_env()
+}
+
+/// Revokes refresh token and access token on the DRACOON server (`/oauth/revoke`)
+pub async fn revoke_refresh_token(base_url: &str, refresh_token: String) -> Result<(), AppError> {
+    let (client_id, client_secret) = get_client_credentials();
+
+    DracoonClient::builder()
+        .with_base_url(base_url)
+        .with_client_id(client_id)
+        .with_client_secret(client_secret)
+        .build()?
+        .connect(OAuth2Flow::refresh_token(refresh_token))
+        .await?
+        .disconnect(Some(true), Some(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Credential store target of an SMTP notification password
+pub fn smtp_password_target(host: &str, username: &str) -> String {
+    format!("smtp://{username}@{host}")
+}
+
+/// Opens the configured credential store for the given target (base url).
+/// If the OS keyring is selected but not available, the encrypted file is used instead.
+pub fn open_credential_store(
+    opts: &CredentialOptions,
+    target: &str,
+) -> Result<Box<dyn HandleCredentials>, AppError> {
+    match opts.store {
+        CredentialStore::Keyring => match Entry::new(SERVICE_NAME, target) {
+            Ok(entry) if is_keyring_available(&entry) => Ok(Box::new(entry)),
+            _ => {
+                warn!("No OS keyring available - using encrypted credential file.");
+                open_file_store(opts, target)
+            }
+        },
+        CredentialStore::File => open_file_store(opts, target),
+    }
+}
+
+pub fn open_file_store(
+    opts: &CredentialOptions,
+    target: &str,
+) -> Result<Box<dyn HandleCredentials>, AppError> {
+    let path = credential_file_path(opts)?;
+
+    let passphrase = match &opts.passphrase {
+        Some(passphrase) => passphrase.clone(),
+        None => prompt_passphrase(&path)?,
+    };
+
+    Ok(Box::new(EncryptedFileStore::new(path, target, passphrase)))
+}
+
+/// Asks for the passphrase of the credential file once if the file store is used -
+/// for commands opening the store for several targets (e.g. `logout --all`)
+pub fn resolve_passphrase(
+    opts: &CredentialOptions,
+    target: &str,
+) -> Result<CredentialOptions, AppError> {
+    let uses_file_store = match opts.store {
+        CredentialStore::Keyring => {
+            !Entry::new(SERVICE_NAME, target).is_ok_and(|entry| is_keyring_available(&entry))
+        }
+        CredentialStore::File => true,
+    };
+
+    if !uses_file_store || opts.passphrase.is_some() {
+        return Ok(opts.clone());
+    }
+
+    let path = credential_file_path(opts)?;
+
+    Ok(CredentialOptions {
+        passphrase: Some(prompt_passphrase(&path)?),
+        ..opts.clone()
+    })
+}
+
+fn credential_file_path(opts: &CredentialOptions) -> Result<PathBuf, AppError> {
+    opts.file
+        .clone()
+        .or_else(EncryptedFileStore::default_path)
+        .ok_or_else(|| {
+            error!("No location for credential file available.");
+            AppError::CredentialStorageFailed
+        })
+}
+
+fn prompt_passphrase(path: &Path) -> Result<String, AppError> {
+    let prompt = dialoguer::Password::new()
+        .with_prompt(format!("Please enter passphrase for {}", path.display()));
+
+    // new file: make sure the passphrase is not mistyped
+    let prompt = if EncryptedFileStore::exists(path) {
+        prompt
+    } else {
+        prompt.with_confirmation("Please confirm passphrase", "Passphrases do not match.")
+    };
+
+    Ok(prompt.interact()?)
+}
+
+// a missing entry is fine - only missing platform support / access is not
+fn is_keyring_available(entry: &Entry) -> bool {
+    !matches!(
+        entry.get_password(),
+        Err(keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryCredentials(RefCell<Option<String>>);
+
+    impl HandleCredentials for MemoryCredentials {
+        fn set_dracoon_env(&self, secret: &str) -> Result<(), AppError> {
+            self.0.replace(Some(secret.to_string()));
+            Ok(())
+        }
+        fn get_dracoon_env(&self) -> Result<String, AppError> {
+            self.0.borrow().clone().ok_or(AppError::InvalidAccount)
+        }
+        fn delete_dracoon_env(&self) -> Result<(), AppError> {
+            self.0
+                .replace(None)
+                .map(|_| ())
+                .ok_or(AppError::InvalidAccount)
+        }
+    }
+
+    #[test]
+    fn test_refresh_token_roundtrip() {
+        let entry = MemoryCredentials::default();
+        entry.set_refresh_token("token").unwrap();
+
+        let stored = entry.get_refresh_token().unwrap();
+        assert_eq!(stored.refresh_token, "token");
+        assert!(stored.refreshed_at.is_some());
+    }
+
+    #[test]
+    fn test_refresh_token_legacy_plain() {
+        let entry = MemoryCredentials::default();
+        entry.set_dracoon_env("token").unwrap();
+
+        assert_eq!(
+            entry.get_refresh_token().unwrap(),
+            StoredRefreshToken {
+                refresh_token: "token".to_string(),
+                refreshed_at: None
+            }
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::cmd::{errors::AppError, SERVICE_NAME};
+
+use super::credentials::HandleCredentials;
+
+const FILE_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const DEFAULT_FILE_NAME: &str = "credentials.enc";
+
+// on-disk format: all secrets are stored as one AES-256-GCM encrypted JSON map
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u8,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Credential store backed by a passphrase encrypted file.
+/// Used on systems without an OS keyring (e.g. headless Linux servers).
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    target: String,
+    passphrase: String,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: impl Into<PathBuf>, target: &str, passphrase: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            target: target.to_string(),
+            passphrase: passphrase.into(),
+        }
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(SERVICE_NAME).join(DEFAULT_FILE_NAME))
+    }
+
+    pub fn exists(path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> Result<[u8; KEY_LEN], AppError> {
+        let mut key = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| {
+                error!("Failed to derive credential file key: {}", e);
+                AppError::CredentialStorageFailed
+            })?;
+
+        Ok(key)
+    }
+
+    fn load(&self) -> Result<HashMap<String, String>, AppError> {
+        if !Self::exists(&self.path) {
+            return Ok(HashMap::new());
+        }
+
+        let content = fs::read(&self.path).map_err(|e| {
+            error!("Failed to read credential file: {}", e);
+            AppError::from(e)
+        })?;
+
+        let file: EncryptedFile = serde_json::from_slice(&content).map_err(|e| {
+            error!("Invalid credential file format: {}", e);
+            AppError::CredentialStorageFailed
+        })?;
+
+        if file.version != FILE_VERSION {
+            error!("Unsupported credential file version: {}", file.version);
+            return Err(AppError::CredentialStorageFailed);
+        }
+
+        let decode = |value: &str| {
+            STANDARD.decode(value).map_err(|e| {
+                error!("Invalid credential file encoding: {}", e);
+                AppError::CredentialStorageFailed
+            })
+        };
+
+        let salt = decode(&file.salt)?;
+        let nonce = decode(&file.nonce)?;
+        let ciphertext = decode(&file.ciphertext)?;
+
+        let key = self.derive_key(&salt)?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| {
+                error!("Failed to decrypt credential file.");
+                AppError::InvalidArgument("Wrong credential file passphrase.".to_string())
+            })?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| {
+            error!("Invalid credential file content: {}", e);
+            AppError::CredentialStorageFailed
+        })
+    }
+
+    fn save(&self, secrets: &HashMap<String, String>) -> Result<(), AppError> {
+        let mut salt = [0u8; SALT_LEN];
+        aes_gcm::aead::rand_core::RngCore::fill_bytes(&mut OsRng, &mut salt);
+
+        let key = self.derive_key(&salt)?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let plaintext =
+            serde_json::to_vec(secrets).map_err(|_| AppError::CredentialStorageFailed)?;
+        let ciphertext = cipher.encrypt(&nonce, plaintext.as_ref()).map_err(|_| {
+            error!("Failed to encrypt credential file.");
+            AppError::CredentialStorageFailed
+        })?;
+
+        let file = EncryptedFile {
+            version: FILE_VERSION,
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+
+        let content =
+            serde_json::to_vec_pretty(&file).map_err(|_| AppError::CredentialStorageFailed)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                error!("Failed to create credential file directory: {}", e);
+                AppError::CredentialStorageFailed
+            })?;
+        }
+
+        // write to a temporary file first to never leave a truncated store behind
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, content).map_err(|e| {
+            error!("Failed to write credential file: {}", e);
+            AppError::CredentialStorageFailed
+        })?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))
+                .map_err(|_| AppError::CredentialStorageFailed)?;
+        }
+
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            error!("Failed to replace credential file: {}", e);
+            AppError::CredentialStorageFailed
+        })
+    }
+}
+
+impl HandleCredentials for EncryptedFileStore {
+    fn set_dracoon_env(&self, secret: &str) -> Result<(), AppError> {
+        let mut secrets = self.load()?;
+        secrets.insert(self.target.clone(), secret.to_string());
+        self.save(&secrets)
+    }
+
+    fn get_dracoon_env(&self) -> Result<String, AppError> {
+        self.load()?
+            .remove(&self.target)
+            .ok_or(AppError::InvalidAccount)
+    }
+
+    fn delete_dracoon_env(&self) -> Result<(), AppError> {
+        let mut secrets = self.load()?;
+
+        if secrets.remove(&self.target).is_none() {
+            return Err(AppError::InvalidAccount);
+        }
+
+        self.save(&secrets)
+            .map_err(|_| AppError::CredentialDeletionFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the store file is removed with the directory when the guard is dropped
+    fn temp_store_path(name: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        (dir, path)
+    }
+
+    #[test]
+    fn test_file_store_roundtrip() {
+        let (_dir, path) = temp_store_path("roundtrip.enc");
+        let store = EncryptedFileStore::new(&path, "https://bla.dracoon.com", "secret");
+
+        store.set_dracoon_env("refresh-token").unwrap();
+        assert_eq!(store.get_dracoon_env().unwrap(), "refresh-token");
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("refresh-token"));
+
+        store.delete_dracoon_env().unwrap();
+        assert_eq!(store.get_dracoon_env(), Err(AppError::InvalidAccount));
+    }
+
+    #[test]
+    fn test_file_store_keeps_other_targets() {
+        let (_dir, path) = temp_store_path("targets.enc");
+        let first = EncryptedFileStore::new(&path, "https://a.dracoon.com", "secret");
+        let second = EncryptedFileStore::new(&path, "https://b.dracoon.com", "secret");
+
+        first.set_dracoon_env("token-a").unwrap();
+        second.set_dracoon_env("token-b").unwrap();
+
+        assert_eq!(first.get_dracoon_env().unwrap(), "token-a");
+        assert_eq!(second.get_dracoon_env().unwrap(), "token-b");
+    }
+
+    #[test]
+    fn test_file_store_wrong_passphrase() {
+        let (_dir, path) = temp_store_path("wrong.enc");
+        EncryptedFileStore::new(&path, "https://bla.dracoon.com", "secret")
+            .set_dracoon_env("refresh-token")
+            .unwrap();
+
+        let store = EncryptedFileStore::new(&path, "https://bla.dracoon.com", "wrong");
+        assert_eq!(
+            store.get_dracoon_env(),
+            Err(AppError::InvalidArgument(
+                "Wrong credential file passphrase.".to_string()
+            ))
+        );
+    }
+}
@@ -1,47 +1,365 @@
-use std::fs::OpenOptions;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
 
+use chrono::{DateTime, Local, NaiveDate};
 use console::Term;
-use tracing::{error, level_filters::LevelFilter};
-use tracing_subscriber::EnvFilter;
+use tracing::{error, level_filters::LevelFilter, Subscriber};
+use tracing_subscriber::{
+    fmt::{format::FmtSpan, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
 
-use crate::cmd::{errors::AppError, handle_error};
+use crate::cmd::{
+    errors::AppError,
+    handle_error,
+    models::{LogFormat, LogOptions, LogRotation},
+};
 
-pub fn init_logging(err_term: &Term, debug: bool, log_file: Option<String>) {
-    let log_format = tracing_subscriber::fmt::format()
-        .with_level(true)
-        .with_source_location(true)
-        .with_target(true)
-        .with_ansi(false)
-        .compact();
-
-    let env_filter = if debug {
+pub fn init_logging(err_term: &Term, opts: LogOptions) {
+    let env_filter = if opts.debug {
         EnvFilter::from_default_env().add_directive(LevelFilter::DEBUG.into())
     } else {
         EnvFilter::from_default_env().add_directive(LevelFilter::INFO.into())
     };
 
     // set up logging file
-    let log_file_path = log_file.unwrap_or("syncoon.log".to_string());
+    let log_file_path = opts.file.unwrap_or("syncoon.log".to_string());
 
-    let log_file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file_path)
+    let log_file = RotatingFile::open(log_file_path, opts.rotation, opts.max_size, opts.retention)
         .map_err(|e| {
             error!("Failed to create or open log file: {}", e);
             AppError::LogFileCreationFailed
         });
 
     if let Err(e) = &log_file {
-        handle_error(err_term, e);
+        handle_error(err_term, e, opts.debug);
     }
 
     let log_file = log_file.unwrap();
 
+    let stderr_layer = opts.stderr.then(|| fmt_layer(opts.format, std::io::stderr));
+
+    #[cfg(feature = "otel")]
+    let otel_layer =
+        opts.otlp_endpoint
+            .as_deref()
+            .and_then(|endpoint| match otel_layer(endpoint) {
+                Ok(layer) => Some(layer),
+                Err(e) => {
+                    err_term
+                        .write_line(&format!("Failed to set up OTLP export: {e}"))
+                        .expect("Error writing error message to terminal.");
+                    None
+                }
+            });
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<Box<dyn Layer<_> + Send + Sync>> = None;
+
     // initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
-        .event_format(log_format)
-        .with_writer(std::sync::Mutex::new(log_file))
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt_layer(opts.format, std::sync::Mutex::new(log_file)))
+        .with(stderr_layer)
+        .with(otel_layer)
         .init();
 }
+
+#[cfg(feature = "otel")]
+static TRACER_PROVIDER: std::sync::OnceLock<opentelemetry_sdk::trace::TracerProvider> =
+    std::sync::OnceLock::new();
+
+/// Flushes pending trace spans (no-op without `otel` feature)
+pub fn shutdown_telemetry() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        // batch processor needs the runtime to export remaining spans
+        if let Err(e) = tokio::task::block_in_place(|| provider.shutdown()) {
+            error!("Failed to export traces: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+fn otel_layer<S>(
+    endpoint: &str,
+) -> Result<Box<dyn Layer<S> + Send + Sync>, opentelemetry::trace::TraceError>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+
+    use crate::cmd::SERVICE_NAME;
+
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')));
+
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace::Config::default().with_resource(Resource::new(vec![
+            KeyValue::new("service.name", SERVICE_NAME),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ])))
+        .install_batch(runtime::Tokio)?;
+
+    let tracer = provider.tracer(SERVICE_NAME);
+    let _ = TRACER_PROVIDER.set(provider);
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer).boxed())
+}
+
+fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_level(true)
+        .with_file(true)
+        .with_line_number(true)
+        .with_target(true)
+        .with_ansi(false);
+
+    match format {
+        LogFormat::Text => layer.compact().boxed(),
+        // span close events carry the span fields and its duration
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+    }
+}
+
+// rotated files are named `<file>.<timestamp>` (millisecond precision)
+const ROTATION_SUFFIX_FORMAT: &str = "%Y%m%d%H%M%S%3f";
+const ROTATION_SUFFIX_LEN: usize = 17;
+
+/// Log file rotated by day or size, keeping `retention` rotated files
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened_on: NaiveDate,
+    rotation: LogRotation,
+    max_size: u64,
+    retention: usize,
+}
+
+impl RotatingFile {
+    pub fn open(
+        path: impl Into<PathBuf>,
+        rotation: LogRotation,
+        max_size: u64,
+        retention: usize,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        let file = open_append(&path)?;
+        let metadata = file.metadata()?;
+
+        // an existing file belongs to the day it was last written
+        let opened_on = metadata
+            .modified()
+            .map(|modified| DateTime::<Local>::from(modified).date_naive())
+            .unwrap_or_else(|_| Local::now().date_naive());
+
+        Ok(Self {
+            path,
+            file,
+            size: metadata.len(),
+            opened_on,
+            rotation,
+            max_size,
+            retention,
+        })
+    }
+
+    fn should_rotate(&self, incoming: usize) -> bool {
+        match self.rotation {
+            LogRotation::Never => false,
+            LogRotation::Daily => Local::now().date_naive() != self.opened_on,
+            LogRotation::Size => self.size > 0 && self.size + incoming as u64 > self.max_size,
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = PathBuf::from(format!(
+            "{}.{}",
+            self.path.display(),
+            Local::now().format(ROTATION_SUFFIX_FORMAT)
+        ));
+
+        self.file.flush()?;
+        fs::rename(&self.path, rotated)?;
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        self.opened_on = Local::now().date_naive();
+
+        self.prune()
+    }
+
+    fn prune(&self) -> io::Result<()> {
+        let rotated = rotated_files(&self.path)?;
+        let excess = rotated.len().saturating_sub(self.retention);
+
+        // rotated file names end with a sortable timestamp - oldest first
+        for path in rotated.into_iter().take(excess) {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate(buf.len()) {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+// only files with the rotation timestamp suffix - e.g. `syncoon.log.bak` is kept
+fn is_rotated_name(name: &str, prefix: &str) -> bool {
+    name.strip_prefix(prefix).is_some_and(|suffix| {
+        suffix.len() == ROTATION_SUFFIX_LEN && suffix.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+fn rotated_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}.",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    let mut rotated = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .is_some_and(|name| is_rotated_name(&name.to_string_lossy(), &prefix))
+        })
+        .collect::<Vec<_>>();
+    rotated.sort();
+
+    Ok(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_rotated_name() {
+        assert!(is_rotated_name(
+            "syncoon.log.20240501120000123",
+            "syncoon.log."
+        ));
+        assert!(!is_rotated_name("syncoon.log.bak", "syncoon.log."));
+        assert!(!is_rotated_name("syncoon.log.tmp", "syncoon.log."));
+        assert!(!is_rotated_name("syncoon.log.2024050112", "syncoon.log."));
+        assert!(!is_rotated_name(
+            "other.log.20240501120000123",
+            "syncoon.log."
+        ));
+    }
+
+    #[test]
+    fn test_size_rotation_with_retention() {
+        let dir = std::env::temp_dir().join(format!("syncoon-log-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+
+        let mut file = RotatingFile::open(&path, LogRotation::Size, 10, 2).unwrap();
+
+        for _ in 0..5 {
+            file.write_all(b"0123456789").unwrap();
+            // rotated file names have millisecond precision
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        fs::write(dir.join("test.log.bak"), b"keep").unwrap();
+        file.write_all(b"0123456789").unwrap();
+
+        assert_eq!(rotated_files(&path).unwrap().len(), 2);
+        assert!(dir.join("test.log.bak").exists());
+        assert_eq!(fs::read(&path).unwrap(), b"0123456789");
+    }
+
+    // local stand-in for the OTLP collector: answers every request with 200 and
+    // returns the request heads it received
+    #[cfg(feature = "otel")]
+    fn spawn_collector() -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::Read;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0; 8192];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let _ = tx.send(String::from_utf8_lossy(&buf[..n]).to_string());
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                );
+            }
+        });
+
+        (endpoint, rx)
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_otel_export() {
+        let (endpoint, requests) = spawn_collector();
+
+        let layer = otel_layer(&format!("{endpoint}/")).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("test_span").in_scope(|| tracing::info!("inside span"));
+        });
+
+        shutdown_telemetry();
+
+        let request = requests
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .unwrap();
+        assert!(request.starts_with("POST /v1/traces "));
+        assert!(request
+            .to_lowercase()
+            .contains("content-type: application/x-protobuf"));
+    }
+}
@@ -1,20 +1,28 @@
 use console::Term;
 use dco3::{Dracoon, OAuth2Flow};
 use dialoguer::Confirm;
-use keyring::Entry;
+use tracing::error;
 
 use self::{
-    credentials::{get_client_credentials, HandleCredentials},
-    models::{ConfigAuthCommand, ConfigCryptoCommand},
+    credentials::{
+        delete_refresh_token, get_client_credentials, open_credential_store, resolve_passphrase,
+        revoke_refresh_token, smtp_password_target, store_refresh_token, HandleCredentials,
+    },
+    models::{ConfigAuthCommand, ConfigCryptoCommand, ConfigSmtpCommand},
+    tenants::list_tenants,
 };
 
 use super::{
-    errors::AppError, models::ConfigCommand, utils::strings::format_error_message, SERVICE_NAME,
+    errors::AppError,
+    models::{ConfigCommand, CredentialOptions},
+    utils::strings::format_error_message,
 };
 
 pub mod credentials;
+pub mod file_store;
 pub mod logs;
 pub mod models;
+pub mod tenants;
 
 pub struct ConfigCommandHandler {
     entry: Box<dyn HandleCredentials>,
@@ -22,20 +30,17 @@ pub struct ConfigCommandHandler {
 }
 
 impl ConfigCommandHandler {
-    pub fn new(entry: impl HandleCredentials + 'static, term: Term) -> Self {
-        Self {
-            entry: Box::new(entry),
-            term,
-        }
+    pub fn new(entry: Box<dyn HandleCredentials>, term: Term) -> Self {
+        Self { entry, term }
     }
 
     pub async fn get_refresh_token_info(&self, target: String) -> Result<(), AppError> {
         let (client_id, client_secret) = get_client_credentials();
-        let Ok(refresh_token) = self.entry.get_dracoon_env() else {
+        let Ok(stored) = self.entry.get_refresh_token() else {
             let msg = format_error_message(
                 format!("No token found for this DRACOON url: {target}.").as_str(),
             );
-            self.term.write_line(&msg).map_err(|_| AppError::IoError)?;
+            self.term.write_line(&msg)?;
             return Err(AppError::InvalidAccount);
         };
 
@@ -44,64 +49,121 @@ impl ConfigCommandHandler {
             .with_client_id(client_id)
             .with_client_secret(client_secret)
             .build()?
-            .connect(OAuth2Flow::refresh_token(refresh_token))
+            .connect(OAuth2Flow::refresh_token(stored.refresh_token))
             .await?;
 
+        // token may have been rotated by the server
+        if store_refresh_token(
+            self.entry.as_ref(),
+            &target,
+            &dracoon.get_refresh_token().await,
+        )
+        .is_err()
+        {
+            error!("Failed to persist refresh token.");
+        }
+
         let user_info = dracoon.get_user_info().await?;
 
         self.term
-            .write_line(&format!("► Token stored for: {target}"))
-            .map_err(|_| AppError::IoError)?;
-        self.term
-            .write_line(&format!(
-                "► User: {} {}",
-                user_info.first_name, user_info.last_name
-            ))
-            .map_err(|_| AppError::IoError)?;
-        self.term
-            .write_line(&format!(
-                "► Email: {}",
-                user_info.email.unwrap_or_else(|| "N/A".to_string())
-            ))
-            .map_err(|_| AppError::IoError)?;
+            .write_line(&format!("► Token stored for: {target}"))?;
+        self.term.write_line(&format!(
+            "► User: {} {}",
+            user_info.first_name, user_info.last_name
+        ))?;
+        self.term.write_line(&format!(
+            "► Email: {}",
+            user_info.email.unwrap_or_else(|| "N/A".to_string())
+        ))?;
         self.term
-            .write_line(&format!("► Username: {}", user_info.user_name))
-            .map_err(|_| AppError::IoError)?;
+            .write_line(&format!("► Username: {}", user_info.user_name))?;
+        self.term.write_line(&format!(
+            "► Last refreshed: {}",
+            stored
+                .refreshed_at
+                .map_or_else(|| "N/A".to_string(), |ts| ts.to_rfc3339())
+        ))?;
 
         Ok(())
     }
 
-    pub fn remove_refresh_token(&self, target: &str) -> Result<(), AppError> {
+    pub async fn remove_refresh_token(&self, target: &str) -> Result<(), AppError> {
         let confirmed = Confirm::new()
             .with_prompt("Are you sure you want to remove the token?")
             .interact_opt();
 
         if let Ok(Some(true)) = confirmed {
-            self.entry.delete_dracoon_env()?;
-            self.term
-                .write_line(&format!("► Token removed for {target}"))
-                .map_err(|_| AppError::IoError)?;
+            self.logout(target).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Revokes the refresh token on the server before deleting it locally
+    pub async fn logout(&self, target: &str) -> Result<(), AppError> {
+        let Ok(stored) = self.entry.get_refresh_token() else {
+            let msg = format_error_message(
+                format!("No token found for this DRACOON url: {target}.").as_str(),
+            );
+            self.term.write_line(&msg)?;
+            return Err(AppError::InvalidAccount);
+        };
+
+        match revoke_refresh_token(target, stored.refresh_token).await {
+            Ok(()) => self
+                .term
+                .write_line(&format!("► Token revoked for {target}"))?,
+            Err(e) => {
+                // token might already be invalid - still remove it locally
+                error!("Failed to revoke token for {}: {}", target, e);
+                let msg = format_error_message(
+                    format!("Token could not be revoked for {target}.").as_str(),
+                );
+                self.term.write_line(&msg)?;
+            }
         }
 
+        delete_refresh_token(self.entry.as_ref(), target)?;
+        self.term
+            .write_line(&format!("► Token removed for {target}"))?;
+
         Ok(())
     }
 
     pub fn get_encryption_secret_info(&self, target: &str) -> Result<(), AppError> {
         let Ok(_) = self.entry.get_dracoon_env() else {
             let msg = format_error_message("No encryption secret found.");
-            self.term.write_line(&msg).map_err(|_| AppError::IoError)?;
+            self.term.write_line(&msg)?;
             return Err(AppError::InvalidAccount);
         };
 
-        self.term
-            .write_line(
-                format!(
-                    "► Encryption secret securely stored for {}.",
-                    target.trim_end_matches("-crypto")
-                )
-                .as_str(),
+        self.term.write_line(
+            format!(
+                "► Encryption secret securely stored for {}.",
+                target.trim_end_matches("-crypto")
             )
-            .map_err(|_| AppError::IoError)?;
+            .as_str(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn set_smtp_password(&self, target: &str) -> Result<(), AppError> {
+        let password = dialoguer::Password::new()
+            .with_prompt(format!("Please enter password for {target}"))
+            .interact()?;
+
+        self.entry.set_dracoon_env(&password)?;
+        self.term
+            .write_line(&format!("► SMTP password stored for {target}."))?;
+
+        Ok(())
+    }
+
+    pub fn remove_smtp_password(&self, target: &str) -> Result<(), AppError> {
+        self.entry.delete_dracoon_env()?;
+        self.term
+            .write_line(&format!("► SMTP password removed for {target}."))?;
 
         Ok(())
     }
@@ -109,62 +171,139 @@ impl ConfigCommandHandler {
     pub fn remove_encryption_secret(&self, target: &str) -> Result<(), AppError> {
         self.entry.delete_dracoon_env()?;
         self.term
-            .write_line(format!("► Encryption secret removed for {target}.").as_str())
-            .map_err(|_| AppError::IoError)?;
+            .write_line(format!("► Encryption secret removed for {target}.").as_str())?;
 
         Ok(())
     }
 }
 
-pub async fn handle_config_cmd(cmd: ConfigCommand, term: Term) -> Result<(), AppError> {
+pub async fn handle_config_cmd(
+    cmd: ConfigCommand,
+    term: Term,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
     match cmd {
         ConfigCommand::Auth { cmd } => match cmd {
             ConfigAuthCommand::Ls { target } => {
-                let (target, entry) = prepare_config_cmd(&target, &term, false)?;
+                let (target, entry) = prepare_config_cmd(&target, &credentials, false)?;
 
                 let handler = ConfigCommandHandler::new(entry, term);
                 handler.get_refresh_token_info(target).await?;
                 Ok(())
             }
             ConfigAuthCommand::Rm { target } => {
-                let (target, entry) = prepare_config_cmd(&target, &term, false)?;
+                let (target, entry) = prepare_config_cmd(&target, &credentials, false)?;
 
                 let handler = ConfigCommandHandler::new(entry, term);
-                handler.remove_refresh_token(&target)?;
+                handler.remove_refresh_token(&target).await?;
                 Ok(())
             }
         },
         ConfigCommand::Crypto { cmd } => match cmd {
             ConfigCryptoCommand::Ls { target } => {
-                let (target, entry) = prepare_config_cmd(&target, &term, true)?;
+                let (target, entry) = prepare_config_cmd(&target, &credentials, true)?;
 
                 let handler = ConfigCommandHandler::new(entry, term);
                 handler.get_encryption_secret_info(&target)?;
                 Ok(())
             }
             ConfigCryptoCommand::Rm { target } => {
-                let (target, entry) = prepare_config_cmd(&target, &term, true)?;
+                let (target, entry) = prepare_config_cmd(&target, &credentials, true)?;
 
                 let handler = ConfigCommandHandler::new(entry, term);
                 handler.remove_encryption_secret(&target)?;
                 Ok(())
             }
         },
+        ConfigCommand::Smtp { cmd } => match cmd {
+            ConfigSmtpCommand::Set { host, username } => {
+                let target = smtp_password_target(&host, &username);
+                let entry = open_credential_store(&credentials, &target)?;
+
+                let handler = ConfigCommandHandler::new(entry, term);
+                handler.set_smtp_password(&target)?;
+                Ok(())
+            }
+            ConfigSmtpCommand::Rm { host, username } => {
+                let target = smtp_password_target(&host, &username);
+                let entry = open_credential_store(&credentials, &target)?;
+
+                let handler = ConfigCommandHandler::new(entry, term);
+                handler.remove_smtp_password(&target)?;
+                Ok(())
+            }
+        },
     }
 }
 
-fn prepare_config_cmd(
-    target: &str,
-    term: &Term,
-    is_crypto: bool,
-) -> Result<(String, impl HandleCredentials), AppError> {
-    let base_url = format!(
+pub async fn handle_logout_cmd(
+    targets: Vec<String>,
+    all: bool,
+    term: Term,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let mut targets = targets
+        .iter()
+        .map(|target| normalize_target(target))
+        .collect::<Vec<_>>();
+
+    if all {
+        targets.extend(list_tenants());
+        targets.sort();
+        targets.dedup();
+
+        // tokens stored before the tenant index was introduced cannot be enumerated
+        term.write_line(
+            "► Only tokens stored by this version are known - log out of older logins by url.",
+        )?;
+    }
+
+    let Some(first) = targets.first() else {
+        term.write_line("► No stored tokens found.")?;
+        return Ok(());
+    };
+
+    // open the credential file once for all targets
+    let credentials = resolve_passphrase(&credentials, first)?;
+
+    let mut res = Ok(());
+
+    // log out of as many tenants as possible, report the first failure
+    for target in targets {
+        let logout = async {
+            let (target, entry) = prepare_config_cmd(&target, &credentials, false)?;
+            ConfigCommandHandler::new(entry, term.clone())
+                .logout(&target)
+                .await
+        };
+
+        if let Err(e) = logout.await {
+            error!("Logout failed for {}: {}", target, e);
+            if res.is_ok() {
+                res = Err(e);
+            }
+        }
+    }
+
+    res
+}
+
+fn normalize_target(target: &str) -> String {
+    format!(
         "https://{}",
         target
             .strip_prefix("https://")
             .unwrap_or(target)
             .trim_end_matches('/')
-    );
+    )
+}
+
+fn prepare_config_cmd(
+    target: &str,
+    credentials: &CredentialOptions,
+    is_crypto: bool,
+) -> Result<(String, Box<dyn HandleCredentials>), AppError> {
+    let base_url = normalize_target(target);
 
     let base_url = if is_crypto {
         format!("{base_url}/-crypto")
@@ -172,12 +311,7 @@ fn prepare_config_cmd(
         base_url
     };
 
-    let Ok(entry) = Entry::new(SERVICE_NAME, &base_url) else {
-        let msg =
-            format_error_message("Secure storage for credentials not available on this platform.");
-        term.write_line(&msg).map_err(|_| AppError::IoError)?;
-        return Err(AppError::CredentialStorageFailed);
-    };
+    let entry = open_credential_store(credentials, &base_url)?;
 
     Ok((base_url, entry))
 }
@@ -29,3 +29,22 @@ pub enum ConfigCryptoCommand {
         target: String,
     },
 }
+
+#[derive(Parser)]
+pub enum ConfigSmtpCommand {
+    /// Store the password of an SMTP notification target (prompted)
+    Set {
+        /// SMTP host as in the notification config
+        host: String,
+        /// SMTP username as in the notification config
+        username: String,
+    },
+
+    /// Remove the password of an SMTP notification target
+    Rm {
+        /// SMTP host as in the notification config
+        host: String,
+        /// SMTP username as in the notification config
+        username: String,
+    },
+}
@@ -0,0 +1,90 @@
+use std::{collections::BTreeSet, fs, path::Path, path::PathBuf};
+
+use tracing::{debug, error};
+
+use crate::cmd::SERVICE_NAME;
+
+const TENANTS_FILE_NAME: &str = "tenants.json";
+
+// index of DRACOON urls with a stored refresh token (keyrings cannot be enumerated)
+fn tenants_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(SERVICE_NAME).join(TENANTS_FILE_NAME))
+}
+
+fn load(path: &Path) -> BTreeSet<String> {
+    fs::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, tenants: &BTreeSet<String>) {
+    let res = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(path, serde_json::to_vec_pretty(tenants).unwrap_or_default()));
+
+    if let Err(e) = res {
+        error!("Failed to update tenant index: {}", e);
+    }
+}
+
+fn update(path: &Path, base_url: &str, stored: bool) {
+    let mut tenants = load(path);
+
+    let changed = if stored {
+        tenants.insert(base_url.to_string())
+    } else {
+        tenants.remove(base_url)
+    };
+
+    if changed {
+        debug!("Updating tenant index for {}", base_url);
+        save(path, &tenants);
+    }
+}
+
+pub fn list_tenants() -> Vec<String> {
+    tenants_path()
+        .map(|path| load(&path).into_iter().collect())
+        .unwrap_or_default()
+}
+
+pub fn register_tenant(base_url: &str) {
+    if let Some(path) = tenants_path() {
+        update(&path, base_url, true);
+    }
+}
+
+pub fn unregister_tenant(base_url: &str) {
+    if let Some(path) = tenants_path() {
+        update(&path, base_url, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenant_index_update() {
+        let path = std::env::temp_dir()
+            .join(format!("{SERVICE_NAME}-test-{}", std::process::id()))
+            .join(TENANTS_FILE_NAME);
+        let _ = fs::remove_file(&path);
+
+        update(&path, "https://b.dracoon.com", true);
+        update(&path, "https://a.dracoon.com", true);
+        update(&path, "https://a.dracoon.com", true);
+        assert_eq!(
+            load(&path).into_iter().collect::<Vec<_>>(),
+            vec!["https://a.dracoon.com", "https://b.dracoon.com"]
+        );
+
+        update(&path, "https://b.dracoon.com", false);
+        assert_eq!(
+            load(&path).into_iter().collect::<Vec<_>>(),
+            vec!["https://a.dracoon.com"]
+        );
+    }
+}
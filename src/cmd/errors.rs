@@ -7,11 +7,11 @@ use thiserror::Error;
 
 #[derive(Debug, PartialEq, Error)]
 pub enum AppError {
-    #[error("Connection to DRACOON failed")]
-    ConnectionFailed,
+    #[error("Connection to DRACOON failed: {0}")]
+    ConnectionFailed(String),
     #[error("Unknown error")]
     Unknown,
-    #[error("Invalid DRACOON url format")]
+    #[error("Invalid DRACOON url format: {0}")]
     InvalidUrl(String),
     #[error("Saving DRACOON credentials failed")]
     CredentialStorageFailed,
@@ -19,28 +19,119 @@ pub enum AppError {
     CredentialDeletionFailed,
     #[error("DRACOON account not found")]
     InvalidAccount,
-    #[error("DRACOON HTTP API error")]
+    #[error("DRACOON HTTP API error: {0}")]
     DracoonError(DracoonErrorResponse),
-    #[error("DRACOON HTTP S3 error")]
+    #[error("DRACOON HTTP S3 error: {0}")]
     DracoonS3Error(Box<S3ErrorResponse>),
-    #[error("DRACOON HTTP authentication error")]
+    #[error("DRACOON HTTP authentication error: {0}")]
     DracoonAuthError(DracoonAuthErrorResponse),
     #[error("IO error")]
-    IoError,
-    #[error("Invalid argument")]
+    IoError(#[source] IoSource),
+    #[error("Invalid argument: {0}")]
     InvalidArgument(String),
     #[error("Log file creation failed")]
     LogFileCreationFailed,
+    #[error("Run partially failed")]
+    PartialSuccess { failed: usize, total: usize },
+    #[error("Missing manage permission on {0} rooms")]
+    MissingManagePermission(usize),
+    #[error("Missing role: {0}")]
+    MissingRole(String),
+    // only logged - notifications are best-effort and never change the exit code
+    #[error("Notification failed: {0}")]
+    NotificationFailed(String),
+    #[error("Drift detected: {0} changes")]
+    DriftDetected(usize),
+    #[error("Aborted: {0}")]
+    Aborted(String),
+    #[error("Fetching room policies failed for {failed} of {total} rooms")]
+    PolicyFetchFailed { failed: usize, total: usize },
+    #[error("Processing room {room_id} failed")]
+    RoomFailed {
+        room_id: u64,
+        #[source]
+        source: Box<AppError>,
+    },
+}
+
+/// `std::io::Error` as error source - compared by kind and message
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct IoSource(#[from] std::io::Error);
+
+impl PartialEq for IoSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind() && self.0.to_string() == other.0.to_string()
+    }
+}
+
+/// Process exit codes per failure class (documented in README), 0 is success
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    InternalError = 1,
+    UsageError = 2,
+    AuthenticationFailed = 3,
+    PermissionDenied = 4,
+    PartialSuccess = 5,
+    ConnectionFailed = 6,
+    DriftDetected = 7,
+    Aborted = 8,
+    PolicyFetchFailed = 9,
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            AppError::InvalidUrl(_) | AppError::InvalidArgument(_) => ExitCode::UsageError,
+            AppError::InvalidAccount | AppError::DracoonAuthError(_) => {
+                ExitCode::AuthenticationFailed
+            }
+            AppError::DracoonError(e) if e.is_unauthorized() => ExitCode::AuthenticationFailed,
+            AppError::DracoonError(e) if e.is_forbidden() => ExitCode::PermissionDenied,
+            AppError::MissingManagePermission(_) | AppError::MissingRole(_) => {
+                ExitCode::PermissionDenied
+            }
+            AppError::PartialSuccess { .. } => ExitCode::PartialSuccess,
+            AppError::ConnectionFailed(_) => ExitCode::ConnectionFailed,
+            AppError::DriftDetected(_) => ExitCode::DriftDetected,
+            AppError::Aborted(_) => ExitCode::Aborted,
+            AppError::PolicyFetchFailed { .. } => ExitCode::PolicyFetchFailed,
+            AppError::RoomFailed { source, .. } => source.exit_code(),
+            AppError::DracoonError(_)
+            | AppError::DracoonS3Error(_)
+            | AppError::CredentialStorageFailed
+            | AppError::CredentialDeletionFailed
+            | AppError::IoError(_)
+            | AppError::LogFileCreationFailed
+            | AppError::NotificationFailed(_)
+            | AppError::Unknown => ExitCode::InternalError,
+        }
+    }
+}
+
+impl AppError {
+    /// IO error for failures outside of `std::io` (e.g. serialization)
+    pub fn io_error(message: impl Into<String>) -> Self {
+        AppError::IoError(std::io::Error::other(message.into()).into())
+    }
+
+    /// Attaches the room being processed to the error
+    pub fn for_room(self, room_id: u64) -> Self {
+        AppError::RoomFailed {
+            room_id,
+            source: Box::new(self),
+        }
+    }
 }
 
 impl From<DracoonClientError> for AppError {
     fn from(value: DracoonClientError) -> Self {
         match value {
-            DracoonClientError::ConnectionFailed(_) => AppError::ConnectionFailed,
+            DracoonClientError::ConnectionFailed(reason) => AppError::ConnectionFailed(reason),
             DracoonClientError::Http(err) => AppError::DracoonError(err),
             DracoonClientError::Auth(err) => AppError::DracoonAuthError(err),
             DracoonClientError::InvalidUrl(url) => AppError::InvalidUrl(url),
-            DracoonClientError::IoError => AppError::IoError,
+            DracoonClientError::IoError => AppError::io_error("DRACOON client IO error"),
             DracoonClientError::S3Error(err) => AppError::DracoonS3Error(err),
             DracoonClientError::MissingArgument => {
                 AppError::InvalidArgument("Missing argument (password set?)".to_string())
@@ -48,7 +139,89 @@ impl From<DracoonClientError> for AppError {
             DracoonClientError::CryptoError(_) => {
                 AppError::InvalidArgument(("Wrong encryption secret.").to_string())
             }
-            _ => AppError::Unknown,
+            DracoonClientError::MissingEncryptionSecret => {
+                AppError::InvalidArgument("Missing encryption secret.".to_string())
+            }
+            DracoonClientError::InvalidPath(path) => {
+                AppError::InvalidArgument(format!("Invalid DRACOON path: {path}"))
+            }
+            DracoonClientError::MissingClientId
+            | DracoonClientError::MissingClientSecret
+            | DracoonClientError::MissingBaseUrl => AppError::InvalidArgument(value.to_string()),
+            DracoonClientError::Unknown | DracoonClientError::Internal => AppError::Unknown,
         }
     }
 }
+
+impl From<std::io::Error> for AppError {
+    fn from(value: std::io::Error) -> Self {
+        AppError::IoError(value.into())
+    }
+}
+
+impl From<dialoguer::Error> for AppError {
+    fn from(value: dialoguer::Error) -> Self {
+        match value {
+            dialoguer::Error::IO(err) => err.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_http_errors() {
+        let forbidden = AppError::DracoonError(DracoonErrorResponse::new(403, "Forbidden"));
+        let unauthorized = AppError::DracoonError(DracoonErrorResponse::new(401, "Unauthorized"));
+        let not_found = AppError::DracoonError(DracoonErrorResponse::new(404, "Not Found"));
+
+        assert_eq!(forbidden.exit_code(), ExitCode::PermissionDenied);
+        assert_eq!(unauthorized.exit_code(), ExitCode::AuthenticationFailed);
+        assert_eq!(not_found.exit_code(), ExitCode::InternalError);
+        assert_eq!(
+            forbidden.for_room(123).exit_code(),
+            ExitCode::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn test_io_error_source() {
+        let err = AppError::from(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "access denied",
+        ));
+
+        let source = std::error::Error::source(&err)
+            .and_then(|source| source.downcast_ref::<IoSource>())
+            .unwrap();
+        assert_eq!(source.0.kind(), std::io::ErrorKind::PermissionDenied);
+        assert_eq!(source.to_string(), "access denied");
+        assert_eq!(err.exit_code(), ExitCode::InternalError);
+    }
+
+    #[test]
+    fn test_exit_code_run_outcome() {
+        let partial = AppError::PartialSuccess {
+            failed: 1,
+            total: 10,
+        };
+
+        assert_eq!(partial.exit_code() as i32, 5);
+        assert_eq!(
+            AppError::ConnectionFailed("Timeout".into()).exit_code() as i32,
+            6
+        );
+        assert_eq!(AppError::InvalidUrl(String::new()).exit_code() as i32, 2);
+        assert_eq!(AppError::Aborted(String::new()).exit_code() as i32, 8);
+        assert_eq!(
+            AppError::PolicyFetchFailed {
+                failed: 1,
+                total: 10
+            }
+            .exit_code() as i32,
+            9
+        );
+    }
+}
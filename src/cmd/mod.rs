@@ -1,32 +1,62 @@
+use chrono::Utc;
 use console::Term;
-use keyring::Entry;
-use tracing::error;
+use dialoguer::Confirm;
+use tracing::{error, info, instrument};
 
 use self::{
-    config::credentials::{get_client_credentials, HandleCredentials},
+    config::credentials::{
+        delete_refresh_token, get_client_credentials, open_credential_store, open_file_store,
+        store_refresh_token, HandleCredentials,
+    },
+    config::logs::shutdown_telemetry,
     errors::AppError,
-    models::PasswordAuth,
+    models::{CredentialOptions, CredentialStore, PasswordAuth},
     utils::strings::format_error_message,
 };
 use dco3::{
-    auth::{Connected, Disconnected, OAuth2Flow},
+    auth::{models::DracoonErrorResponse, Connected, Disconnected, OAuth2Flow},
     Dracoon, DracoonBuilder,
 };
 
 pub mod config;
 pub mod errors;
 pub mod models;
+pub mod notify;
 pub mod remover;
 pub mod utils;
 
 // service name to store
 const SERVICE_NAME: &str = env!("CARGO_PKG_NAME");
 
+/// Connected DRACOON client and the credential entry holding its refresh token
+pub struct DracoonSession {
+    pub dracoon: Dracoon<Connected>,
+    base_url: String,
+    entry: Option<Box<dyn HandleCredentials>>,
+}
+
+impl DracoonSession {
+    /// Persists the current (possibly rotated) refresh token
+    pub async fn persist_refresh_token(&self) {
+        let Some(entry) = &self.entry else {
+            return;
+        };
+
+        let refresh_token = self.dracoon.get_refresh_token().await;
+        match store_refresh_token(entry.as_ref(), &self.base_url, &refresh_token) {
+            Ok(()) => info!("Refresh token refreshed at {}", Utc::now().to_rfc3339()),
+            Err(_) => error!("Failed to persist refresh token."),
+        }
+    }
+}
+
+#[instrument(skip(password_auth, credentials))]
 async fn init_dracoon(
     url_path: &str,
     password_auth: Option<PasswordAuth>,
+    credentials: &CredentialOptions,
     is_transfer: bool,
-) -> Result<Dracoon<Connected>, AppError> {
+) -> Result<DracoonSession, AppError> {
     let (client_id, client_secret) = get_client_credentials();
     let base_url = parse_base_url(url_path.to_string())?;
 
@@ -43,62 +73,104 @@ async fn init_dracoon(
         .with_user_agent(syncoon_user_agent)
         .build()?;
 
-    let entry = Entry::new(SERVICE_NAME, &base_url).map_err(|_| AppError::CredentialStorageFailed);
-
     // Always use password auth first if present
     if let Some(password_auth) = password_auth {
-        return authenticate_password_flow(dracoon, password_auth).await;
+        let dracoon = authenticate_password_flow(dracoon, password_auth).await?;
+        return Ok(DracoonSession {
+            dracoon,
+            base_url,
+            entry: None,
+        });
     }
     // Entry not present & no password auth? Game over.
-    let Ok(entry) = entry else {
-        error!("Can't open keyring entry for {}", base_url);
-        return Err(AppError::CredentialStorageFailed);
-    };
+    let entry = open_credential_store(credentials, &base_url)
+        .inspect_err(|_| error!("Can't open credential store for {}", base_url))?;
 
     // Attempt to use refresh token if exists
-    if let Ok(refresh_token) = entry.get_dracoon_env() {
+    if let Ok(stored) = entry.get_refresh_token() {
         if let Ok(dracoon) = dracoon
             .clone()
-            .connect(OAuth2Flow::RefreshToken(refresh_token))
+            .connect(OAuth2Flow::RefreshToken(stored.refresh_token))
             .await
         {
-            return Ok(dracoon);
+            let session = DracoonSession {
+                dracoon,
+                base_url,
+                entry: Some(entry),
+            };
+            // token may have been rotated by the server
+            session.persist_refresh_token().await;
+            return Ok(session);
         }
         // Refresh token didn't work, delete it
-        let _ = entry.delete_dracoon_env();
+        let _ = delete_refresh_token(entry.as_ref(), &base_url);
     }
 
     // Final resort: auth code flow
-    authenticate_auth_code_flow(dracoon, entry).await
+    authenticate_auth_code_flow(dracoon, entry, credentials, base_url).await
 }
 
+#[instrument(skip_all)]
 async fn authenticate_auth_code_flow(
     dracoon: Dracoon<Disconnected>,
-    entry: Entry,
-) -> Result<Dracoon<Connected>, AppError> {
+    entry: Box<dyn HandleCredentials>,
+    credentials: &CredentialOptions,
+    base_url: String,
+) -> Result<DracoonSession, AppError> {
     println!("Please log in via browser (open url): ");
     println!("{}", dracoon.get_authorize_url());
 
     let auth_code = dialoguer::Password::new()
         .with_prompt("Please enter authorization code")
-        .interact()
-        .or(Err(AppError::IoError))?;
+        .interact()?;
 
     let dracoon = dracoon
         .connect(OAuth2Flow::AuthCodeFlow(auth_code.trim_end().into()))
         .await?;
 
-    // TODO: if this fails, offer to store in plain
-    let res = entry.set_dracoon_env(&dracoon.get_refresh_token().await);
-    match res {
-        Ok(_) => Ok(dracoon),
-        Err(_) => {
-            error!("Failed to store refresh token in keyring.");
-            Ok(dracoon)
+    let refresh_token = dracoon.get_refresh_token().await;
+    if store_refresh_token(entry.as_ref(), &base_url, &refresh_token).is_ok() {
+        return Ok(DracoonSession {
+            dracoon,
+            base_url,
+            entry: Some(entry),
+        });
+    }
+
+    error!("Failed to store refresh token in keyring.");
+
+    // keyring failed: offer to store in encrypted file instead
+    if credentials.store == CredentialStore::Keyring {
+        let store_in_file = Confirm::new()
+            .with_prompt(
+                "Failed to store refresh token in keyring. Store it in an encrypted file instead?",
+            )
+            .interact_opt();
+
+        if let Ok(Some(true)) = store_in_file {
+            let file_entry = open_file_store(credentials, &base_url)?;
+            match store_refresh_token(file_entry.as_ref(), &base_url, &refresh_token) {
+                Ok(()) => {
+                    info!("Stored refresh token in encrypted credential file.");
+                    return Ok(DracoonSession {
+                        dracoon,
+                        base_url,
+                        entry: Some(file_entry),
+                    });
+                }
+                Err(_) => error!("Failed to store refresh token in encrypted credential file."),
+            }
         }
     }
+
+    Ok(DracoonSession {
+        dracoon,
+        base_url,
+        entry: None,
+    })
 }
 
+#[instrument(skip_all)]
 async fn authenticate_password_flow(
     dracoon: Dracoon<Disconnected>,
     password_auth: PasswordAuth,
@@ -130,7 +202,7 @@ fn parse_base_url(url_str: String) -> Result<String, AppError> {
     }
 }
 
-pub fn handle_error(term: &Term, err: &AppError) {
+pub fn handle_error(term: &Term, err: &AppError, debug: bool) {
     let err_msg = get_error_message(err);
     let err_msg = format_error_message(&err_msg);
 
@@ -139,30 +211,113 @@ pub fn handle_error(term: &Term, err: &AppError) {
     term.write_line(&err_msg)
         .expect("Error writing error message to terminal.");
 
-    // exit with error code
-    std::process::exit(1);
+    if debug {
+        for cause in get_error_chain(err) {
+            error!("{}", cause);
+            term.write_line(&format!("  ▶︎ {cause}"))
+                .expect("Error writing error message to terminal.");
+        }
+    }
+
+    shutdown_telemetry();
+
+    // exit with error code per failure class
+    std::process::exit(err.exit_code() as i32);
 }
 
 fn get_error_message(err: &AppError) -> String {
     match err {
         AppError::InvalidUrl(url) => format!("Invalid URL: {url}"),
-        AppError::IoError => "Error reading / writing content.".into(),
-        AppError::DracoonError(e) => format!("{e}"),
-        AppError::ConnectionFailed => "Connection failed.".into(),
+        AppError::IoError(e) => format!("Error reading / writing content: {e}"),
+        AppError::DracoonError(e) => match get_http_error_hint(e) {
+            Some(hint) => format!("{} {}: {hint}", e.code(), e.error_message()),
+            None => format!("{e}"),
+        },
+        AppError::ConnectionFailed(reason) => {
+            format!("Connection failed ({reason}): check the DRACOON url and your network.")
+        }
         AppError::CredentialDeletionFailed => "Credential deletion failed.".into(),
-        AppError::CredentialStorageFailed => "Credential store failed.".into(),
-        AppError::InvalidAccount => "Invalid account.".into(),
+        AppError::CredentialStorageFailed => {
+            "Credential store failed: no OS keyring available? Try --credential-store file.".into()
+        }
+        AppError::InvalidAccount => {
+            "Invalid account: no valid token stored - run again to log in via browser.".into()
+        }
         AppError::Unknown => "Unknown error.".into(),
         AppError::DracoonS3Error(e) => format!("{e}"),
-        AppError::DracoonAuthError(e) => format!("{e}"),
+        AppError::DracoonAuthError(e) => {
+            format!("{e}: token expired or revoked - log in again.")
+        }
         AppError::InvalidArgument(msg) => msg.to_string(),
         AppError::LogFileCreationFailed => "Log file creation failed.".into(),
+        AppError::PartialSuccess { failed, total } => {
+            format!("{failed} of {total} updates failed (see log for details).")
+        }
+        AppError::MissingManagePermission(rooms) => format!(
+            "Missing room admin rights (manage permission) on {rooms} rooms - use --skip-unmanaged to skip them."
+        ),
+        AppError::MissingRole(role) => {
+            format!("Missing role {role}: ask a DRACOON admin to assign it.")
+        }
+        AppError::NotificationFailed(reason) => format!("Sending notification failed: {reason}"),
+        AppError::DriftDetected(changes) => {
+            format!("Drift detected: {changes} changes compared to the baseline.")
+        }
+        AppError::Aborted(reason) => format!("Aborted - {reason}."),
+        AppError::PolicyFetchFailed { failed, total } => format!(
+            "Fetching the policies of {failed} of {total} rooms failed (see log for details) - no result written."
+        ),
+        AppError::RoomFailed { room_id, source } => match source.as_ref() {
+            AppError::DracoonError(e) => format!(
+                "{} on room {room_id}: {}",
+                e.code(),
+                get_http_error_hint(e).unwrap_or_else(|| e.error_message())
+            ),
+            source => format!("Room {room_id}: {}", get_error_message(source)),
+        },
+    }
+}
+
+// actionable hint per HTTP error class
+fn get_http_error_hint(err: &DracoonErrorResponse) -> Option<String> {
+    let hint = match err.code() {
+        401 => "token expired or revoked - log in again",
+        403 => "you need room admin rights (manage permission)",
+        404 => "room not found or not visible for the current user",
+        429 => "too many requests - try again later",
+        500.. => "DRACOON server error - try again later",
+        _ => return None,
+    };
+
+    Some(match err.error_code() {
+        Some(code) => format!("{hint} (DRACOON error code {code})"),
+        None => hint.to_string(),
+    })
+}
+
+// full error chain incl. sources and API debug info
+fn get_error_chain(err: &AppError) -> Vec<String> {
+    let mut chain = std::iter::successors(Some(err as &dyn std::error::Error), |e| e.source())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    if let Some(AppError::DracoonError(e)) = std::iter::successors(Some(err), |e| match e {
+        AppError::RoomFailed { source, .. } => Some(source.as_ref()),
+        _ => None,
+    })
+    .last()
+    {
+        if let Some(debug_info) = e.debug_info() {
+            chain.push(format!("Debug info: {debug_info}"));
+        }
     }
+
+    chain
 }
 
 pub fn print_version(term: &Term) -> Result<(), AppError> {
     term.write_line(get_version().as_str())
-        .map_err(|_| AppError::IoError)
+        .map_err(AppError::from)
 }
 
 pub fn get_version() -> String {
@@ -188,6 +343,27 @@ mod tests {
         assert_eq!(base_url, "https://bla.dracoon.com");
     }
 
+    #[test]
+    fn test_room_error_message_with_hint() {
+        let err = AppError::DracoonError(DracoonErrorResponse::new(403, "Forbidden")).for_room(123);
+        assert_eq!(
+            get_error_message(&err),
+            "403 on room 123: you need room admin rights (manage permission)"
+        );
+    }
+
+    #[test]
+    fn test_error_chain() {
+        let err = AppError::DracoonError(DracoonErrorResponse::new(404, "Not Found")).for_room(1);
+        assert_eq!(
+            get_error_chain(&err),
+            vec![
+                "Processing room 1 failed",
+                "DRACOON HTTP API error: 404 Not Found - No details (0)"
+            ]
+        );
+    }
+
     #[test]
     fn test_base_url_parse_invalid_path() {
         let base_url = parse_base_url("bla.dracoon.com".into());
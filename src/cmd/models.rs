@@ -1,26 +1,378 @@
-use clap::Parser;
+use std::{convert::Infallible, path::PathBuf, str::FromStr};
 
-use super::config::models::{ConfigAuthCommand, ConfigCryptoCommand};
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::{Parser, ValueEnum};
+
+use super::{
+    config::models::{ConfigAuthCommand, ConfigCryptoCommand, ConfigSmtpCommand},
+    utils::{
+        dates::{parse_date, parse_duration},
+        strings::parse_size,
+    },
+};
+
+// room given on the command line: either a room id or a path (`/Clients/ACME`) / name (`ACME`)
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoomRef {
+    Id(u64),
+    Path(String),
+}
+
+impl FromStr for RoomRef {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(value
+            .parse::<u64>()
+            .map_or_else(|_| RoomRef::Path(value.to_string()), RoomRef::Id))
+    }
+}
+
+// deleting user of a recycle bin entry: `system` (e.g. expiration), a user id or a user name
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeletedBy {
+    System,
+    UserId(i64),
+    UserName(String),
+}
+
+impl FromStr for DeletedBy {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("system") {
+            return Ok(DeletedBy::System);
+        }
+
+        Ok(value.parse::<i64>().map_or_else(
+            |_| DeletedBy::UserName(value.to_string()),
+            DeletedBy::UserId,
+        ))
+    }
+}
+
+// user given on the command line: either a user id or a user name
+#[derive(Clone, Debug, PartialEq)]
+pub enum UserRef {
+    Id(i64),
+    Name(String),
+}
+
+impl FromStr for UserRef {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(value
+            .parse::<i64>()
+            .map_or_else(|_| UserRef::Name(value.to_string()), UserRef::Id))
+    }
+}
+
+// new expiration of a bulk change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpirationChange {
+    Remove,
+    ExtendTo(DateTime<Utc>),
+    // relative to the current expiration (or now if already expired)
+    ExtendBy(TimeDelta),
+}
+
+impl ExpirationChange {
+    pub fn new(
+        remove: bool,
+        extend_to: Option<DateTime<Utc>>,
+        extend_by: Option<TimeDelta>,
+    ) -> Self {
+        match (remove, extend_to, extend_by) {
+            (_, Some(date), _) => ExpirationChange::ExtendTo(date),
+            (_, None, Some(duration)) => ExpirationChange::ExtendBy(duration),
+            _ => ExpirationChange::Remove,
+        }
+    }
+
+    /// New expiration date - `None` removes the expiration
+    pub fn apply(&self, current: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ExpirationChange::Remove => None,
+            ExpirationChange::ExtendTo(date) => Some(*date),
+            ExpirationChange::ExtendBy(duration) => Some(current.max(now) + *duration),
+        }
+    }
+}
+
+// share types to process
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ShareKind {
+    /// download and upload shares
+    #[default]
+    All,
+    /// download shares (share links)
+    Download,
+    /// upload shares (file requests)
+    Upload,
+}
+
+// account types to process
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum AccountKind {
+    /// users and groups
+    #[default]
+    All,
+    /// user accounts
+    Users,
+    /// groups
+    Groups,
+}
+
+// handling of name conflicts when restoring deleted nodes
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ConflictStrategy {
+    /// keep both - the restored node is renamed
+    #[default]
+    Autorename,
+    /// replace the existing node
+    Overwrite,
+    /// do not restore the node
+    Fail,
+}
+
+// output format of reports
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// human-readable text
+    #[default]
+    Text,
+    /// JSON document (e.g. for automation)
+    Json,
+}
+
+// output format of listings
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ReportFormat {
+    /// aligned columns
+    #[default]
+    Table,
+    /// comma separated values with header row
+    Csv,
+    /// JSON array
+    Json,
+}
 
 // represents password flow
 #[derive(Clone)]
 pub struct PasswordAuth(pub String, pub String);
 
+// backend used to persist refresh tokens and encryption secrets
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum CredentialStore {
+    /// OS keyring (falls back to file if no keyring is available)
+    #[default]
+    Keyring,
+    /// passphrase encrypted file
+    File,
+}
+
+// format of log records
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// human-readable compact lines
+    #[default]
+    Text,
+    /// one JSON object per record (incl. span fields)
+    Json,
+}
+
+// rotation policy of the log file
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum LogRotation {
+    /// never rotate
+    #[default]
+    Never,
+    /// rotate once per day
+    Daily,
+    /// rotate when the file exceeds --log-max-size
+    Size,
+}
+
+// represents logging configuration
+#[derive(Clone)]
+pub struct LogOptions {
+    pub debug: bool,
+    pub stderr: bool,
+    pub file: Option<String>,
+    pub format: LogFormat,
+    pub rotation: LogRotation,
+    pub max_size: u64,
+    pub retention: usize,
+    #[cfg(feature = "otel")]
+    pub otlp_endpoint: Option<String>,
+}
+
+// represents credential store selection
+#[derive(Clone, Default)]
+pub struct CredentialOptions {
+    pub store: CredentialStore,
+    pub file: Option<PathBuf>,
+    pub passphrase: Option<String>,
+}
+
+// represents notification profile selection
+#[derive(Clone, Debug, Default)]
+pub struct NotifyOptions {
+    pub config: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+// number of updated and failed items of a bulk update (e.g. rooms of a run)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UpdateCount {
+    pub updated: usize,
+    pub failed: usize,
+}
+
+// represents the room selection and safeguards of a run
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    pub data_room_ids: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub skip_unmanaged: bool,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub stream: bool,
+}
+
+// represents the watched rooms and scan interval of watch mode
+#[derive(Clone, Debug, Default)]
+pub struct WatchOptions {
+    pub rooms: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub interval: u64,
+}
+
+// represents the room selection and filters of a recycle bin recovery
+#[derive(Clone, Debug, Default)]
+pub struct RecoverOptions {
+    pub rooms: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub deleted_after: Option<DateTime<Utc>>,
+    pub deleted_before: Option<DateTime<Utc>>,
+    pub deleted_by: Option<DeletedBy>,
+    pub all_versions: bool,
+    pub on_conflict: ConflictStrategy,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub format: OutputFormat,
+}
+
+// represents the room selection and time window of the expiring files report
+#[derive(Clone, Debug, Default)]
+pub struct ExpiringOptions {
+    pub rooms: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub within: TimeDelta,
+    pub format: ReportFormat,
+}
+
+// represents the room selection and date range of the policy history report
+#[derive(Clone, Debug, Default)]
+pub struct HistoryOptions {
+    pub rooms: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub operations: Vec<i64>,
+    pub format: ReportFormat,
+}
+
+// represents the share selection and expiration change of a shares run
+#[derive(Clone, Debug)]
+pub struct ShareOptions {
+    pub rooms: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub kind: ShareKind,
+    pub expires_before: Option<DateTime<Utc>>,
+    pub created_by: Option<UserRef>,
+    pub change: ExpirationChange,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub format: OutputFormat,
+}
+
+// represents the account selection and expiration change of a users run
+#[derive(Clone, Debug)]
+pub struct AccountOptions {
+    pub rooms: Vec<RoomRef>,
+    pub kind: AccountKind,
+    pub expires_before: Option<DateTime<Utc>>,
+    pub change: ExpirationChange,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub format: OutputFormat,
+}
+
+// room configuration to apply - `None` keeps the current value
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoomSettings {
+    pub recycle_bin_retention: Option<u32>,
+    pub quota: Option<u64>,
+    pub inherit_permissions: Option<bool>,
+}
+
+// represents the rooms and settings of a rooms configure run
+#[derive(Clone, Debug, Default)]
+pub struct RoomConfigOptions {
+    pub rooms: Vec<RoomRef>,
+    pub all_managed: bool,
+    pub settings: RoomSettings,
+    pub skip_unmanaged: bool,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub format: OutputFormat,
+}
+
 #[derive(Parser)]
 #[clap(rename_all = "kebab-case", about = "Syncoon (dccmd-rs)")]
 pub struct Syncoon {
     #[clap(subcommand)]
     pub cmd: SyncoonCommand,
 
+    /// debug logging and full error chain output
     #[clap(long, global = true)]
     pub debug: bool,
 
+    /// also write logs to stderr
     #[clap(long, global = true)]
     pub log_file_out: bool,
 
     #[clap(long, global = true)]
     pub log_file_path: Option<String>,
 
+    /// log record format
+    #[clap(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// log file rotation policy
+    #[clap(long, global = true, value_enum, default_value_t = LogRotation::Never)]
+    pub log_rotation: LogRotation,
+
+    /// max. log file size in MB (for size based rotation, 1 - 1048576)
+    #[clap(
+        long,
+        global = true,
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..=1024 * 1024)
+    )]
+    pub log_max_size: u64,
+
+    /// number of rotated log files to keep
+    #[clap(long, global = true, default_value_t = 7)]
+    pub log_retention: usize,
+
+    /// export traces via OTLP/HTTP to this collector endpoint
+    #[cfg(feature = "otel")]
+    #[clap(long, global = true, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+
     /// optional username
     #[clap(long, global = true)]
     pub username: Option<String>,
@@ -32,6 +384,37 @@ pub struct Syncoon {
     /// optional encryption password
     #[clap(long, global = true)]
     pub encryption_password: Option<String>,
+
+    /// credential store for refresh tokens and encryption secrets
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        env = "REMOVE_EXPIRATION_CREDENTIAL_STORE",
+        default_value_t = CredentialStore::Keyring
+    )]
+    pub credential_store: CredentialStore,
+
+    /// optional path to the encrypted credential file
+    #[clap(long, global = true, env = "REMOVE_EXPIRATION_CREDENTIAL_FILE")]
+    pub credential_file: Option<PathBuf>,
+
+    /// optional passphrase for the encrypted credential file
+    #[clap(
+        long,
+        global = true,
+        env = "REMOVE_EXPIRATION_CREDENTIAL_PASSPHRASE",
+        hide_env_values = true
+    )]
+    pub credential_passphrase: Option<String>,
+
+    /// optional path to the notification config (JSON)
+    #[clap(long, global = true, env = "REMOVE_EXPIRATION_NOTIFY_CONFIG")]
+    pub notify_config: Option<PathBuf>,
+
+    /// notification profile to use after a run (default: `default` if configured)
+    #[clap(long, global = true, env = "REMOVE_EXPIRATION_NOTIFY_PROFILE")]
+    pub notify_profile: Option<String>,
 }
 
 #[derive(Parser)]
@@ -40,9 +423,277 @@ pub enum SyncoonCommand {
         // Source DRACOON instance
         dracoon: String,
 
-        // Target DRACOON instance
-        data_room_id: u64,
+        // Parent room ids or paths, e.g. 149 150 /Clients/ACME (omit to pick rooms interactively)
+        data_room_ids: Vec<RoomRef>,
+
+        /// update all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "data_room_ids")]
+        all_managed: bool,
+
+        /// skip rooms without manage permission instead of asking
+        #[clap(long)]
+        skip_unmanaged: bool,
+
+        /// skip the confirmation prompt (required without TTY)
+        #[clap(long, short)]
+        yes: bool,
+
+        /// list the rooms that would be updated without modifying them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// update rooms while they are discovered (huge room trees, requires --skip-unmanaged)
+        #[clap(long, requires = "skip_unmanaged", conflicts_with = "dry_run")]
+        stream: bool,
+    },
+    /// Continuously remove expiration policies re-added to rooms
+    Watch {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths to watch
+        #[clap(required_unless_present = "all_managed")]
+        rooms: Vec<RoomRef>,
+
+        /// watch all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// seconds between two scans
+        #[clap(long, default_value = "300", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
     },
+    /// Export the room tree and expiration policies as baseline for `diff`
+    Baseline {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths to export
+        #[clap(required_unless_present = "all_managed")]
+        rooms: Vec<RoomRef>,
+
+        /// export all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// baseline file to write
+        #[clap(long, short)]
+        output: PathBuf,
+    },
+    /// Compare the room tree with a baseline (exit code 7 on drift)
+    Diff {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Baseline file exported with `baseline`
+        baseline: PathBuf,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Restore deleted files from the recycle bins of the rooms (e.g. files removed by expiration)
+    Recover {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths (omit to pick rooms interactively)
+        rooms: Vec<RoomRef>,
+
+        /// recover in all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// only nodes deleted at or after this date (YYYY-MM-DD or RFC 3339)
+        #[clap(long, value_parser = parse_date)]
+        deleted_after: Option<DateTime<Utc>>,
+
+        /// only nodes deleted before this date (YYYY-MM-DD or RFC 3339)
+        #[clap(long, value_parser = parse_date)]
+        deleted_before: Option<DateTime<Utc>>,
+
+        /// only nodes deleted by `system` (e.g. expiration), a user id or a user name
+        #[clap(long)]
+        deleted_by: Option<DeletedBy>,
+
+        /// restore all matching versions instead of the most recently deleted one per node
+        #[clap(long)]
+        all_versions: bool,
+
+        /// handling of nodes with the same name in the target folder
+        #[clap(long, value_enum, default_value_t)]
+        on_conflict: ConflictStrategy,
+
+        /// skip the confirmation prompt (required without TTY)
+        #[clap(long, short)]
+        yes: bool,
+
+        /// list the versions that would be restored without restoring them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// List files expiring within a time window (e.g. to warn room admins)
+    Expiring {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths (omit to pick rooms interactively)
+        rooms: Vec<RoomRef>,
+
+        /// report files in all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// time window from now, e.g. 12h, 14d or 2w
+        #[clap(long, default_value = "14d", value_parser = parse_duration)]
+        within: TimeDelta,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: ReportFormat,
+    },
+    /// Remove or extend the expiration of download and upload shares in the rooms
+    #[clap(group(clap::ArgGroup::new("change").required(true)))]
+    Shares {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths (omit to pick rooms interactively)
+        rooms: Vec<RoomRef>,
+
+        /// process shares in all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// share types to process
+        #[clap(long, value_enum, default_value_t)]
+        kind: ShareKind,
+
+        /// only shares expiring before this date (YYYY-MM-DD or RFC 3339)
+        #[clap(long, value_parser = parse_date)]
+        expires_before: Option<DateTime<Utc>>,
+
+        /// only shares created by this user (user id or user name)
+        #[clap(long)]
+        created_by: Option<UserRef>,
+
+        /// remove the expiration
+        #[clap(long, group = "change")]
+        remove: bool,
+
+        /// set the expiration to this date (YYYY-MM-DD or RFC 3339)
+        #[clap(long, group = "change", value_parser = parse_date)]
+        extend_to: Option<DateTime<Utc>>,
+
+        /// extend the expiration by a duration, e.g. 30d (from now if already expired)
+        #[clap(long, group = "change", value_parser = parse_duration)]
+        extend_by: Option<TimeDelta>,
+
+        /// skip the confirmation prompt (required without TTY)
+        #[clap(long, short)]
+        yes: bool,
+
+        /// list the shares that would be updated without modifying them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Clear or extend the expiration of user and group accounts (requires user / group manager role)
+    #[clap(group(clap::ArgGroup::new("change").required(true)))]
+    Users {
+        // Source DRACOON instance
+        dracoon: String,
+
+        /// only members of the rooms below this parent room (id or path, repeatable)
+        #[clap(long = "room")]
+        rooms: Vec<RoomRef>,
+
+        /// account types to process
+        #[clap(long, value_enum, default_value_t)]
+        kind: AccountKind,
+
+        /// only accounts expiring before this date (YYYY-MM-DD or RFC 3339)
+        #[clap(long, value_parser = parse_date)]
+        expires_before: Option<DateTime<Utc>>,
+
+        /// remove the expiration
+        #[clap(long, group = "change")]
+        remove: bool,
+
+        /// set the expiration to this date (YYYY-MM-DD or RFC 3339)
+        #[clap(long, group = "change", value_parser = parse_date)]
+        extend_to: Option<DateTime<Utc>>,
+
+        /// extend the expiration by a duration, e.g. 30d (from now if already expired)
+        #[clap(long, group = "change", value_parser = parse_duration)]
+        extend_by: Option<TimeDelta>,
+
+        /// skip the confirmation prompt (required without TTY)
+        #[clap(long, short)]
+        yes: bool,
+
+        /// list the accounts that would be updated without modifying them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Manage the configuration of the collected rooms
+    Rooms {
+        #[clap(subcommand)]
+        cmd: RoomsCommand,
+    },
+    /// List who changed the room policies from the event log (requires auditor role)
+    ///
+    /// The event log records no old and new policy values: the first two event attributes are
+    /// reported as logged by DRACOON, their meaning depends on the operation.
+    History {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths (omit to pick rooms interactively)
+        rooms: Vec<RoomRef>,
+
+        /// report changes in all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// start of the date range (YYYY-MM-DD or RFC 3339, default: 30 days before --to)
+        #[clap(long, value_parser = parse_date)]
+        from: Option<DateTime<Utc>>,
+
+        /// end of the date range, exclusive (YYYY-MM-DD or RFC 3339, default: now)
+        #[clap(long, value_parser = parse_date)]
+        to: Option<DateTime<Utc>>,
+
+        /// event log operation id to report instead of the known policy operations (repeatable)
+        #[clap(long = "operation")]
+        operations: Vec<i64>,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: ReportFormat,
+    },
+    /// Revoke and remove stored DRACOON tokens
+    Logout {
+        /// DRACOON urls (may be combined with --all for logins stored by older versions)
+        #[clap(required_unless_present = "all")]
+        targets: Vec<String>,
+
+        /// log out of all DRACOON urls recorded with a stored token
+        #[clap(long)]
+        all: bool,
+    },
+
     /// Configure syncoon-cli
     Config {
         #[clap(subcommand)]
@@ -53,6 +704,51 @@ pub enum SyncoonCommand {
     Version,
 }
 
+#[derive(Parser)]
+pub enum RoomsCommand {
+    /// Apply recycle bin retention, quota and permission inheritance to the collected rooms
+    #[clap(group(clap::ArgGroup::new("settings").required(true).multiple(true)))]
+    Configure {
+        // Source DRACOON instance
+        dracoon: String,
+
+        // Parent room ids or paths (omit to pick rooms interactively)
+        rooms: Vec<RoomRef>,
+
+        /// configure all rooms the user holds manage permission on
+        #[clap(long, conflicts_with = "rooms")]
+        all_managed: bool,
+
+        /// days deleted files are kept in the recycle bin (0: deleted immediately)
+        #[clap(long, group = "settings", value_parser = clap::value_parser!(u32).range(0..=9999))]
+        recycle_bin_retention: Option<u32>,
+
+        /// room quota, e.g. 500M or 10G (0: no quota)
+        #[clap(long, group = "settings", value_parser = parse_size)]
+        quota: Option<u64>,
+
+        /// inherit permissions from the parent room (true / false, root rooms are skipped)
+        #[clap(long, group = "settings")]
+        inherit_permissions: Option<bool>,
+
+        /// skip rooms without manage permission without prompting
+        #[clap(long)]
+        skip_unmanaged: bool,
+
+        /// skip the confirmation prompt (required without TTY)
+        #[clap(long, short)]
+        yes: bool,
+
+        /// list the changes per room without modifying any room
+        #[clap(long)]
+        dry_run: bool,
+
+        /// report format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+}
+
 #[derive(Parser)]
 pub enum ConfigCommand {
     /// Manage Syncoon auth credentials (refresh token)
@@ -66,4 +762,10 @@ pub enum ConfigCommand {
         #[clap(subcommand)]
         cmd: ConfigCryptoCommand,
     },
+
+    /// Manage SMTP notification passwords
+    Smtp {
+        #[clap(subcommand)]
+        cmd: ConfigSmtpCommand,
+    },
 }
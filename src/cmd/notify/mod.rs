@@ -0,0 +1,511 @@
+use std::{collections::HashMap, fs, path::PathBuf, process::Stdio, time::Duration};
+
+use console::Term;
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, error, info, instrument};
+
+use super::{
+    config::credentials::{open_credential_store, smtp_password_target},
+    errors::AppError,
+    get_error_message,
+    models::{CredentialOptions, NotifyOptions},
+    utils::strings::format_error_message,
+    SERVICE_NAME,
+};
+
+mod models;
+
+pub use models::RunReport;
+use models::{NotificationTarget, NotifyConfig, SmtpSecurity, SmtpTarget};
+
+const CONFIG_FILE_NAME: &str = "notifications.json";
+const DEFAULT_PROFILE: &str = "default";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends the run report to all targets of the selected profile.
+/// Notifications are best-effort: failures are logged and shown but never change the exit code.
+#[instrument(skip_all, fields(status = ?report.status))]
+pub async fn send_notifications(
+    term: &Term,
+    opts: &NotifyOptions,
+    credentials: &CredentialOptions,
+    report: &RunReport,
+) {
+    let targets = match load_targets(opts) {
+        Ok(targets) => targets,
+        Err(e) => {
+            error!("Failed to load notification config: {}", e);
+            let _ = term.write_line(&format_error_message(&get_error_message(&e)));
+            return;
+        }
+    };
+
+    for target in &targets {
+        let res = match target {
+            NotificationTarget::Webhook { url, headers } => {
+                send_webhook(url, headers, report).await
+            }
+            NotificationTarget::Smtp(smtp) => match smtp_password(smtp, credentials) {
+                Ok(password) => send_email(smtp, password, report).await,
+                Err(e) => Err(e),
+            },
+            NotificationTarget::Command { program, args } => {
+                run_command(program, args, report).await
+            }
+        };
+
+        match res {
+            Ok(()) => info!("Notification sent via {}", target.kind()),
+            Err(e) => {
+                error!("Notification via {} failed: {}", target.kind(), e);
+                let _ = term.write_line(&format_error_message(&get_error_message(&e)));
+            }
+        }
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(SERVICE_NAME).join(CONFIG_FILE_NAME))
+}
+
+// notifications are optional - only an explicitly selected profile must exist
+fn load_targets(opts: &NotifyOptions) -> Result<Vec<NotificationTarget>, AppError> {
+    let Some(path) = opts.config.clone().or_else(default_config_path) else {
+        return Ok(vec![]);
+    };
+
+    if !path.is_file() {
+        if opts.config.is_some() || opts.profile.is_some() {
+            return Err(AppError::InvalidArgument(format!(
+                "Notification config not found: {}",
+                path.display()
+            )));
+        }
+
+        debug!("No notification config at {}", path.display());
+        return Ok(vec![]);
+    }
+
+    let config: NotifyConfig = serde_json::from_slice(&fs::read(&path)?)
+        .map_err(|e| AppError::InvalidArgument(format!("Invalid notification config: {e}")))?;
+
+    select_profile(config, opts.profile.as_deref())
+}
+
+fn select_profile(
+    mut config: NotifyConfig,
+    profile: Option<&str>,
+) -> Result<Vec<NotificationTarget>, AppError> {
+    let name = profile.unwrap_or(DEFAULT_PROFILE);
+
+    match config.profiles.remove(name) {
+        Some(targets) => Ok(targets),
+        None if profile.is_none() => Ok(vec![]),
+        None => Err(AppError::InvalidArgument(format!(
+            "Notification profile not found: {name}"
+        ))),
+    }
+}
+
+async fn send_webhook(
+    url: &str,
+    headers: &HashMap<String, String>,
+    report: &RunReport,
+) -> Result<(), AppError> {
+    let failed = |e: reqwest::Error| AppError::NotificationFailed(format!("webhook {url}: {e}"));
+
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(failed)?;
+
+    let mut request = client.post(url).json(report);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(failed)?;
+
+    Ok(())
+}
+
+fn smtp_password(
+    smtp: &SmtpTarget,
+    credentials: &CredentialOptions,
+) -> Result<Option<String>, AppError> {
+    if smtp.password.is_some() {
+        return Err(AppError::NotificationFailed(format!(
+            "smtp {}: plain passwords are not supported - remove it from the config and store it via `config smtp set`",
+            smtp.host
+        )));
+    }
+
+    let Some(username) = &smtp.username else {
+        return Ok(None);
+    };
+
+    let target = smtp_password_target(&smtp.host, username);
+    open_credential_store(credentials, &target)?
+        .get_dracoon_env()
+        .map(Some)
+        .map_err(|_| {
+            AppError::NotificationFailed(format!(
+                "smtp {}: no password stored for {username} - run `config smtp set`",
+                smtp.host
+            ))
+        })
+}
+
+async fn send_email(
+    smtp: &SmtpTarget,
+    password: Option<String>,
+    report: &RunReport,
+) -> Result<(), AppError> {
+    let failed = |e: &dyn std::fmt::Display| {
+        AppError::NotificationFailed(format!("smtp {}: {e}", smtp.host))
+    };
+
+    let mut message = Message::builder()
+        .from(smtp.from.parse().map_err(|e| failed(&e))?)
+        .subject(report.subject());
+    for to in &smtp.to {
+        message = message.to(to.parse().map_err(|e| failed(&e))?);
+    }
+    let message = message.body(report.text()).map_err(|e| failed(&e))?;
+
+    let mut transport = match smtp.security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host),
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+            &smtp.host,
+        )),
+    }
+    .map_err(|e| failed(&e))?
+    .timeout(Some(TIMEOUT));
+
+    if let Some(port) = smtp.port {
+        transport = transport.port(port);
+    }
+    if let (Some(username), Some(password)) = (&smtp.username, password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| failed(&e))?;
+
+    Ok(())
+}
+
+async fn run_command(program: &str, args: &[String], report: &RunReport) -> Result<(), AppError> {
+    let failed =
+        |e: &dyn std::fmt::Display| AppError::NotificationFailed(format!("command {program}: {e}"));
+
+    let payload = serde_json::to_vec(report).map_err(|e| failed(&e))?;
+
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| failed(&e))?;
+
+    // stdin is closed when dropped - the command sees EOF
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&payload).await.map_err(|e| failed(&e))?;
+    }
+
+    let status = tokio::time::timeout(TIMEOUT, child.wait())
+        .await
+        .map_err(|e| failed(&e))?
+        .map_err(|e| failed(&e))?;
+
+    if !status.success() {
+        return Err(failed(&status));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{models::RunStatus, *};
+    use crate::cmd::models::UpdateCount;
+
+    #[test]
+    fn test_select_profile() {
+        let config = r#"{
+            "profiles": {
+                "default": [
+                    { "type": "webhook", "url": "http://localhost:8080/hook" },
+                    { "type": "command", "program": "logger", "args": ["-t", "remove-expiration"] }
+                ],
+                "ops": [
+                    { "type": "smtp", "host": "localhost", "port": 2525, "security": "none",
+                      "from": "cli@example.com", "to": ["ops@example.com"] }
+                ]
+            }
+        }"#;
+        let parse = || serde_json::from_str::<NotifyConfig>(config).unwrap();
+
+        assert_eq!(select_profile(parse(), None).unwrap().len(), 2);
+        assert_eq!(
+            select_profile(parse(), Some("ops")).unwrap()[0].kind(),
+            "smtp"
+        );
+        assert!(select_profile(parse(), Some("missing")).is_err());
+        assert!(select_profile(NotifyConfig::default(), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_run_report_from_result() {
+        let tenant = "https://bla.dracoon.com";
+        let count = UpdateCount {
+            updated: 8,
+            failed: 2,
+        };
+        let partial = Err(AppError::PartialSuccess {
+            failed: 2,
+            total: 10,
+        });
+        let report = RunReport::new(tenant, count, &partial, Duration::from_secs(1));
+
+        assert_eq!(report.updated, 8);
+        assert_eq!(report.failed, 2);
+        assert_eq!(report.duration_ms, 1000);
+        assert_eq!(
+            serde_json::to_value(&report).unwrap()["status"],
+            "partial_success"
+        );
+
+        let report = RunReport::new(tenant, UpdateCount::default(), &Ok(()), Duration::ZERO);
+        assert_eq!(
+            report.subject(),
+            "remove-expiration run on https://bla.dracoon.com: no changes"
+        );
+
+        // confirmation declined
+        let aborted = Err(AppError::Aborted("no rooms modified".to_string()));
+        let report = RunReport::new(tenant, UpdateCount::default(), &aborted, Duration::ZERO);
+        assert_eq!(report.status, RunStatus::NoChanges);
+        assert!(report.error.is_none());
+
+        // rooms updated before the run failed are reported
+        let count = UpdateCount {
+            updated: 4,
+            failed: 0,
+        };
+        let failed = Err(AppError::ConnectionFailed("Timeout".to_string()));
+        let report = RunReport::new(tenant, count, &failed, Duration::ZERO);
+        assert_eq!(report.status, RunStatus::Failed);
+        assert_eq!(report.updated, 4);
+
+        // all updates failed
+        let count = UpdateCount {
+            updated: 0,
+            failed: 3,
+        };
+        let failed = Err(AppError::MissingManagePermission(3));
+        let report = RunReport::new(tenant, count, &failed, Duration::ZERO);
+        assert_eq!(report.failed, 3);
+    }
+
+    fn report() -> RunReport {
+        let count = UpdateCount {
+            updated: 3,
+            failed: 0,
+        };
+        RunReport::new(
+            "https://bla.dracoon.com",
+            count,
+            &Ok(()),
+            Duration::from_secs(2),
+        )
+    }
+
+    // local stand-in for the webhook receiver: returns the raw request
+    async fn serve_http(status: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+
+            // headers, then the body announced by content-length
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|len| len.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length || n == 0 {
+                        break;
+                    }
+                }
+            }
+
+            stream
+                .write_all(format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook() {
+        let (url, server) = serve_http("200 OK").await;
+        let headers = HashMap::from([("X-Token".to_string(), "secret".to_string())]);
+
+        send_webhook(&url, &headers, &report()).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-token: secret"));
+        let body = request.split_once("\r\n\r\n").unwrap().1;
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["status"], "success");
+        assert_eq!(payload["updated"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_error_status() {
+        let (url, server) = serve_http("500 Internal Server Error").await;
+
+        let res = send_webhook(&url, &HashMap::new(), &report()).await;
+
+        assert!(matches!(res, Err(AppError::NotificationFailed(_))));
+        server.await.unwrap();
+    }
+
+    // local stand-in for an SMTP server (no TLS, no auth): returns the message data
+    async fn serve_smtp() -> (u16, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let mut data = String::new();
+
+            write.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let command = line.to_uppercase();
+                let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+                    b"250 localhost\r\n"
+                } else if command.starts_with("DATA") {
+                    write.write_all(b"354 end with .\r\n").await.unwrap();
+                    while let Some(line) = lines.next_line().await.unwrap() {
+                        if line == "." {
+                            break;
+                        }
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    b"250 queued\r\n"
+                } else if command.starts_with("QUIT") {
+                    write.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 OK\r\n"
+                };
+                write.write_all(reply).await.unwrap();
+            }
+
+            data
+        });
+
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn test_send_email() {
+        let (port, server) = serve_smtp().await;
+        let smtp = SmtpTarget {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "cli@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+        };
+
+        send_email(&smtp, None, &report()).await.unwrap();
+
+        let data = server.await.unwrap();
+        assert!(data.contains("To: ops@example.com"));
+        assert!(data.contains("Subject: remove-expiration run on https://bla.dracoon.com: success"));
+        assert!(data.contains("Rooms updated: 3"));
+    }
+
+    #[test]
+    fn test_smtp_plain_password_rejected() {
+        let smtp = serde_json::from_value::<SmtpTarget>(serde_json::json!({
+            "host": "localhost", "username": "cli", "password": "secret",
+            "from": "cli@example.com", "to": ["ops@example.com"]
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            smtp_password(&smtp, &CredentialOptions::default()),
+            Err(AppError::NotificationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_command() {
+        let path = std::env::temp_dir().join(format!("syncoon-notify-{}", std::process::id()));
+        let args = vec![
+            "-c".to_string(),
+            "cat > \"$0\"".to_string(),
+            path.display().to_string(),
+        ];
+
+        run_command("sh", &args, &report()).await.unwrap();
+
+        let payload: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(payload["tenant"], "https://bla.dracoon.com");
+        assert_eq!(payload["updated"], 3);
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_run_command_exit_status() {
+        let args = vec!["-c".to_string(), "cat > /dev/null; exit 3".to_string()];
+
+        let res = run_command("sh", &args, &report()).await;
+
+        assert!(matches!(res, Err(AppError::NotificationFailed(_))));
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::{errors::AppError, get_error_message, models::UpdateCount};
+
+/// Notification config file: named profiles with a list of targets each
+#[derive(Debug, Default, Deserialize)]
+pub struct NotifyConfig {
+    pub profiles: HashMap<String, Vec<NotificationTarget>>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationTarget {
+    /// HTTP POST with the run report as JSON payload
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// plain text email
+    Smtp(SmtpTarget),
+    /// local command receiving the run report as JSON on stdin
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl NotificationTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NotificationTarget::Webhook { .. } => "webhook",
+            NotificationTarget::Smtp(_) => "smtp",
+            NotificationTarget::Command { .. } => "command",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct SmtpTarget {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    // the password is kept in the credential store (`config smtp set`)
+    pub username: Option<String>,
+    // plain passwords are rejected
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Success,
+    // nothing to update, dry run or aborted
+    NoChanges,
+    PartialSuccess,
+    Failed,
+}
+
+/// Summary of a run sent to all notification targets
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub tenant: String,
+    pub status: RunStatus,
+    pub updated: usize,
+    pub failed: usize,
+    pub duration_ms: u64,
+    pub finished_at: DateTime<Utc>,
+    pub error: Option<String>,
+}
+
+impl RunReport {
+    pub fn new(
+        tenant: &str,
+        count: UpdateCount,
+        res: &Result<(), AppError>,
+        duration: Duration,
+    ) -> Self {
+        let (status, error) = match res {
+            Ok(()) if count.updated == 0 => (RunStatus::NoChanges, None),
+            Ok(()) => (RunStatus::Success, None),
+            Err(AppError::Aborted(_)) => (RunStatus::NoChanges, None),
+            Err(e @ AppError::PartialSuccess { .. }) => {
+                (RunStatus::PartialSuccess, Some(get_error_message(e)))
+            }
+            Err(e) => (RunStatus::Failed, Some(get_error_message(e))),
+        };
+
+        Self {
+            tenant: tenant.to_string(),
+            status,
+            updated: count.updated,
+            failed: count.failed,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            finished_at: Utc::now(),
+            error,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        let status = match self.status {
+            RunStatus::Success => "success",
+            RunStatus::NoChanges => "no changes",
+            RunStatus::PartialSuccess => "partial success",
+            RunStatus::Failed => "failed",
+        };
+
+        format!("remove-expiration run on {}: {status}", self.tenant)
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = format!(
+            "{}\n\nRooms updated: {}\nRooms failed: {}\nDuration: {} ms\nFinished at: {}\n",
+            self.subject(),
+            self.updated,
+            self.failed,
+            self.duration_ms,
+            self.finished_at.to_rfc3339()
+        );
+
+        if let Some(error) = &self.error {
+            text.push_str(&format!("Error: {error}\n"));
+        }
+
+        text
+    }
+}
@@ -0,0 +1,333 @@
+use console::Term;
+use dco3::{
+    auth::Connected,
+    nodes::{ConfigRoomRequest, Node, UpdateRoomRequest},
+    Dracoon, Nodes, Rooms,
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use tracing::{debug, info, info_span, instrument};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{CredentialOptions, OutputFormat, PasswordAuth, RoomConfigOptions, RoomSettings},
+    utils::strings::to_readable_size,
+};
+
+use super::{
+    collect_rooms, confirm::confirm_bulk, get_parent_ids, logged_update,
+    managed::collect_managed_rooms, nodes::node_path, permissions::check_manage_permissions,
+    BulkReport,
+};
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Diff<T> {
+    old: Option<T>,
+    new: T,
+}
+
+// settings of a room that differ from the requested ones
+#[derive(Debug, PartialEq, Serialize)]
+struct RoomConfigChange {
+    id: u64,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recycle_bin_retention: Option<Diff<u32>>,
+    // 0: no quota
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quota: Option<Diff<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inherit_permissions: Option<Diff<bool>>,
+}
+
+impl RoomConfigChange {
+    fn has_config_change(&self) -> bool {
+        self.recycle_bin_retention.is_some() || self.inherit_permissions.is_some()
+    }
+}
+
+// `None` if the room already has the requested settings
+fn plan_change(room: &Node, settings: RoomSettings) -> Option<RoomConfigChange> {
+    let current_retention = room
+        .recycle_bin_retention_period
+        .and_then(|days| u32::try_from(days).ok());
+    let recycle_bin_retention = settings
+        .recycle_bin_retention
+        .filter(|new| current_retention != Some(*new))
+        .map(|new| Diff {
+            old: current_retention,
+            new,
+        });
+
+    // no quota is returned as either missing or 0
+    let quota = settings
+        .quota
+        .filter(|new| room.quota.unwrap_or(0) != *new)
+        .map(|new| Diff {
+            old: room.quota.filter(|quota| *quota > 0),
+            new,
+        });
+
+    // root rooms cannot inherit permissions
+    let inherit_permissions = settings
+        .inherit_permissions
+        .filter(|_| room.parent_id.is_some())
+        .filter(|new| room.inherit_permissions != Some(*new))
+        .map(|new| Diff {
+            old: room.inherit_permissions,
+            new,
+        });
+
+    if recycle_bin_retention.is_none() && quota.is_none() && inherit_permissions.is_none() {
+        return None;
+    }
+
+    Some(RoomConfigChange {
+        id: room.id,
+        path: node_path(room),
+        recycle_bin_retention,
+        quota,
+        inherit_permissions,
+    })
+}
+
+fn print_changes(term: &Term, rooms: &[RoomConfigChange]) -> Result<(), AppError> {
+    term.write_line(&format!("► Rooms to configure: {}", rooms.len()))?;
+
+    let days = |days: u32| format!("{days} days");
+    let quota = |quota: u64| match quota {
+        0 => "none".to_string(),
+        quota => to_readable_size(quota),
+    };
+    let yes_no = |value: bool| if value { "yes" } else { "no" }.to_string();
+
+    for room in rooms {
+        term.write_line(&format!("  ▶︎ {} ({})", room.path, room.id))?;
+
+        if let Some(diff) = &room.recycle_bin_retention {
+            print_diff(term, "recycle bin retention", diff, days)?;
+        }
+        if let Some(diff) = &room.quota {
+            print_diff(term, "quota", diff, quota)?;
+        }
+        if let Some(diff) = &room.inherit_permissions {
+            print_diff(term, "inherit permissions", diff, yes_no)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_diff<T: Copy>(
+    term: &Term,
+    setting: &str,
+    diff: &Diff<T>,
+    fmt: impl Fn(T) -> String,
+) -> Result<(), AppError> {
+    term.write_line(&format!(
+        "      {setting}: {} → {}",
+        diff.old.map_or_else(|| "none".to_string(), &fmt),
+        fmt(diff.new)
+    ))?;
+
+    Ok(())
+}
+
+/// Applies recycle bin retention, quota and permission inheritance to the collected rooms
+#[instrument(name = "rooms_configure", skip(term, auth, credentials))]
+pub async fn handle_configure(
+    term: Term,
+    base_url: String,
+    opts: RoomConfigOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = configure_rooms(&term, &dracoon, opts).await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+async fn configure_rooms(
+    term: &Term,
+    dracoon: &Dracoon<Connected>,
+    opts: RoomConfigOptions,
+) -> Result<(), AppError> {
+    let rooms = if opts.all_managed {
+        collect_managed_rooms(dracoon.clone()).await?
+    } else {
+        let parent_ids = get_parent_ids(dracoon, term, &opts.rooms).await?;
+        collect_rooms(dracoon.clone(), &parent_ids).await?
+    };
+
+    let rooms = check_manage_permissions(term, rooms, opts.skip_unmanaged)?;
+    let rooms = load_settings(dracoon, rooms, opts.settings).await?;
+
+    let report = BulkReport::new(
+        "rooms",
+        opts.dry_run,
+        rooms
+            .iter()
+            .filter_map(|room| plan_change(room, opts.settings))
+            .collect(),
+    );
+    info!(
+        "{} of {} rooms differ from the requested configuration",
+        report.changes.len(),
+        rooms.len()
+    );
+
+    if report.changes.is_empty() {
+        return match opts.format {
+            OutputFormat::Text => Ok(term.write_line("► No rooms to configure.")?),
+            OutputFormat::Json => report.print(term, opts.format),
+        };
+    }
+
+    if opts.format == OutputFormat::Text {
+        print_changes(term, &report.changes)?;
+    }
+
+    if opts.dry_run {
+        info!("Dry run - {} rooms not modified", report.changes.len());
+        return report.print(term, opts.format);
+    }
+
+    let prompt = format!("Configure {} rooms?", report.changes.len());
+    if !confirm_bulk(term, prompt, opts.yes)? {
+        return Err(AppError::Aborted("no rooms modified".to_string()));
+    }
+
+    report
+        .apply(term, opts.format, |room| configure_room(dracoon, room))
+        .await
+}
+
+// the node search does not return the room configuration - rooms without the requested
+// settings are loaded again (10 concurrent requests)
+async fn load_settings(
+    dracoon: &Dracoon<Connected>,
+    rooms: Vec<Node>,
+    settings: RoomSettings,
+) -> Result<Vec<Node>, AppError> {
+    let is_incomplete = |room: &Node| {
+        (settings.recycle_bin_retention.is_some() && room.recycle_bin_retention_period.is_none())
+            || (settings.inherit_permissions.is_some() && room.inherit_permissions.is_none())
+            || (settings.quota.is_some() && room.quota.is_none())
+    };
+
+    if !rooms.iter().any(is_incomplete) {
+        return Ok(rooms);
+    }
+
+    let room_stream = stream::iter(rooms)
+        .map(|room| async move {
+            if is_incomplete(&room) {
+                debug!("Loading configuration of room {}", room.id);
+                Ok::<_, AppError>(dracoon.nodes().get_node(room.id).await?)
+            } else {
+                Ok(room)
+            }
+        })
+        .buffered(10);
+
+    room_stream.try_collect().await
+}
+
+async fn configure_room(
+    dracoon: &Dracoon<Connected>,
+    room: &RoomConfigChange,
+) -> Result<(), AppError> {
+    let span = info_span!("configure_room", room_id = room.id);
+
+    let update = async {
+        if room.has_config_change() {
+            let mut config = ConfigRoomRequest::builder();
+            if let Some(diff) = &room.recycle_bin_retention {
+                config = config.with_recycle_bin_retention_period(diff.new);
+            }
+            if let Some(diff) = &room.inherit_permissions {
+                // keep the current permissions when the inheritance is removed
+                config = config
+                    .with_inherit_permissions(diff.new)
+                    .with_take_over_permissions(!diff.new);
+            }
+            dracoon.nodes().config_room(room.id, config.build()).await?;
+        }
+
+        if let Some(diff) = &room.quota {
+            let update = UpdateRoomRequest::builder().with_quota(diff.new).build();
+            dracoon.nodes().update_room(room.id, update).await?;
+        }
+
+        Ok(())
+    };
+
+    logged_update(span, "Room configuration", update)
+        .await
+        .map_err(|e| AppError::from(e).for_room(room.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::room as room_fixture;
+
+    fn room(retention: u64, quota: Option<u64>, inherit: bool, parent_id: Option<u64>) -> Node {
+        room_fixture(
+            12,
+            serde_json::json!({
+                "name": "ACME", "parentPath": "/Clients/", "parentId": parent_id, "recycleBinRetentionPeriod": retention, "quota": quota,
+                "inheritPermissions": inherit
+            }),
+        )
+    }
+
+    #[test]
+    fn test_plan_change() {
+        let settings = RoomSettings {
+            recycle_bin_retention: Some(90),
+            quota: Some(0),
+            inherit_permissions: Some(false),
+        };
+
+        let change = plan_change(&room(30, Some(1024), true, Some(10)), settings).unwrap();
+        assert_eq!(change.path, "/Clients/ACME");
+        assert_eq!(
+            change.recycle_bin_retention,
+            Some(Diff {
+                old: Some(30),
+                new: 90
+            })
+        );
+        assert_eq!(
+            change.quota,
+            Some(Diff {
+                old: Some(1024),
+                new: 0
+            })
+        );
+        assert_eq!(
+            change.inherit_permissions,
+            Some(Diff {
+                old: Some(true),
+                new: false
+            })
+        );
+
+        // already configured - no quota may be returned as 0
+        assert!(plan_change(&room(90, Some(0), false, Some(10)), settings).is_none());
+
+        // root rooms keep their permissions
+        let settings = RoomSettings {
+            inherit_permissions: Some(true),
+            ..Default::default()
+        };
+        assert!(plan_change(&room(90, None, false, None), settings).is_none());
+    }
+}
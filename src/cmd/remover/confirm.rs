@@ -0,0 +1,195 @@
+use console::{style, Term};
+use dialoguer::{Confirm, Input};
+use tracing::info;
+
+use crate::cmd::errors::AppError;
+
+const SAMPLE_SIZE: usize = 5;
+
+// summary shown before any room is modified
+pub struct RunSummary {
+    pub tenant: String,
+    pub user: String,
+    pub parent: String,
+    pub room_names: Vec<String>,
+}
+
+impl RunSummary {
+    fn print_header(&self, term: &Term) -> Result<(), AppError> {
+        term.write_line(&format!("► Tenant: {}", self.tenant))?;
+        term.write_line(&format!("► User: {}", self.user))?;
+        term.write_line(&format!("► Parent room: {}", self.parent))?;
+
+        Ok(())
+    }
+
+    fn print(&self, term: &Term) -> Result<(), AppError> {
+        self.print_header(term)?;
+        term.write_line(&format!(
+            "► Rooms to update: {}",
+            style(self.room_names.len()).bold()
+        ))?;
+
+        for name in self.room_names.iter().take(SAMPLE_SIZE) {
+            term.write_line(&format!("  ▶︎ {name}"))?;
+        }
+
+        if self.room_names.len() > SAMPLE_SIZE {
+            term.write_line(&format!(
+                "  ▶︎ ... and {} more",
+                self.room_names.len() - SAMPLE_SIZE
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Shows the run summary and asks to confirm by typing the room count.
+/// Returns `false` if the user declined.
+pub fn confirm_run(term: &Term, summary: &RunSummary, yes: bool) -> Result<bool, AppError> {
+    summary.print(term)?;
+
+    if yes {
+        info!("Confirmation skipped (--yes)");
+        return Ok(true);
+    }
+
+    if !term.is_term() {
+        return Err(AppError::InvalidArgument(
+            "Refusing to modify rooms without confirmation (no TTY) - use --yes.".to_string(),
+        ));
+    }
+
+    let room_count = summary.room_names.len();
+    let input: String = Input::new()
+        .with_prompt(format!(
+            "Type the number of rooms ({room_count}) to confirm"
+        ))
+        .allow_empty(true)
+        .interact_text_on(term)?;
+
+    Ok(is_confirmed(&input, room_count))
+}
+
+fn is_confirmed(input: &str, room_count: usize) -> bool {
+    input.trim() == room_count.to_string()
+}
+
+/// Shows the run summary before a streamed run (the rooms are not known yet) and asks to confirm.
+/// Returns `false` if the user declined.
+pub fn confirm_stream_run(term: &Term, summary: &RunSummary, yes: bool) -> Result<bool, AppError> {
+    summary.print_header(term)?;
+
+    confirm_bulk(
+        term,
+        "Remove the expiration policies of all rooms found (updated while they are discovered)?"
+            .to_string(),
+        yes,
+    )
+}
+
+/// Shows the run summary and all rooms that would be updated - no room is modified.
+pub fn print_dry_run(term: &Term, summary: &RunSummary) -> Result<(), AppError> {
+    summary.print(term)?;
+
+    term.write_line("► Dry run - rooms that would be updated:")?;
+    for name in &summary.room_names {
+        term.write_line(&format!("  ▶︎ {name}"))?;
+    }
+
+    info!("Dry run - {} rooms not modified", summary.room_names.len());
+    term.write_line("► Dry run - no rooms modified.")?;
+
+    Ok(())
+}
+
+/// Asks to confirm a bulk change (e.g. "Restore 12 deleted versions?").
+/// Returns `false` if the user declined.
+pub fn confirm_bulk(term: &Term, prompt: String, yes: bool) -> Result<bool, AppError> {
+    if yes {
+        info!("Confirmation skipped (--yes)");
+        return Ok(true);
+    }
+
+    if !term.is_term() {
+        return Err(AppError::InvalidArgument(
+            "Refusing to apply changes without confirmation (no TTY) - use --yes.".to_string(),
+        ));
+    }
+
+    Ok(Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact_on(term)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::FileTerm;
+
+    fn summary(rooms: usize) -> RunSummary {
+        RunSummary {
+            tenant: "https://dracoon.team/".to_string(),
+            user: "Jane Doe (jdoe)".to_string(),
+            parent: "Clients (10)".to_string(),
+            room_names: (0..rooms).map(|i| format!("Room {i}")).collect(),
+        }
+    }
+
+    #[test]
+    fn test_is_confirmed() {
+        assert!(is_confirmed("12", 12));
+        assert!(is_confirmed(" 12\n", 12));
+        assert!(!is_confirmed("", 12));
+        assert!(!is_confirmed("y", 12));
+        assert!(!is_confirmed("11", 12));
+    }
+
+    #[test]
+    fn test_confirm_run_yes() {
+        let file_term = FileTerm::new();
+        let term = &file_term.term;
+
+        assert_eq!(confirm_run(term, &summary(7), true), Ok(true));
+
+        let output = file_term.output();
+        assert!(output.contains("Rooms to update: 7"));
+        assert!(output.contains("Room 4"));
+        assert!(!output.contains("Room 5"));
+        assert!(output.contains("... and 2 more"));
+    }
+
+    #[test]
+    fn test_confirm_stream_run() {
+        let file_term = FileTerm::new();
+        let term = &file_term.term;
+
+        assert_eq!(confirm_stream_run(term, &summary(0), true), Ok(true));
+        assert!(matches!(
+            confirm_stream_run(term, &summary(0), false),
+            Err(AppError::InvalidArgument(_))
+        ));
+
+        // rooms are not known yet
+        let output = file_term.output();
+        assert!(output.contains("Parent room: Clients (10)"));
+        assert!(!output.contains("Rooms to update"));
+    }
+
+    #[test]
+    fn test_confirm_run_without_tty() {
+        let file_term = FileTerm::new();
+        let term = &file_term.term;
+
+        assert!(matches!(
+            confirm_run(term, &summary(3), false),
+            Err(AppError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            confirm_bulk(term, "Configure 3 rooms?".to_string(), false),
+            Err(AppError::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,401 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use console::Term;
+use dco3::{auth::Connected, nodes::RoomPolicies, Dracoon, DracoonClientError};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument, warn};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{CredentialOptions, OutputFormat, PasswordAuth, RoomRef},
+};
+
+use super::{
+    collect_rooms,
+    managed::collect_managed_rooms,
+    nodes::{get_room_policies, node_path},
+    resolve::resolve_room,
+};
+
+const BASELINE_VERSION: u8 = 1;
+
+/// Room tree and expiration policies at export time
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    version: u8,
+    tenant: String,
+    created_at: DateTime<Utc>,
+    // `None` covers all rooms with manage permission
+    parent_ids: Option<Vec<u64>>,
+    rooms: Vec<RoomSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    id: u64,
+    path: String,
+    // `None` if the policies may not be read (missing manage permission)
+    expiration_period: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Renamed {
+    id: u64,
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ExpirationChanged {
+    id: u64,
+    path: String,
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct DriftReport {
+    added: Vec<RoomSnapshot>,
+    removed: Vec<RoomSnapshot>,
+    renamed: Vec<Renamed>,
+    expiration_changed: Vec<ExpirationChanged>,
+}
+
+impl DriftReport {
+    fn count(&self) -> usize {
+        self.added.len() + self.removed.len() + self.renamed.len() + self.expiration_changed.len()
+    }
+
+    fn print(&self, term: &Term) -> Result<(), AppError> {
+        if self.count() == 0 {
+            term.write_line("► No drift detected.")?;
+            return Ok(());
+        }
+
+        term.write_line(&format!("► Drift detected: {} changes", self.count()))?;
+
+        for room in &self.added {
+            term.write_line(&format!("  + added: {} ({})", room.path, room.id))?;
+        }
+        for room in &self.removed {
+            term.write_line(&format!("  - removed: {} ({})", room.path, room.id))?;
+        }
+        for room in &self.renamed {
+            term.write_line(&format!(
+                "  ~ renamed: {} → {} ({})",
+                room.from, room.to, room.id
+            ))?;
+        }
+        for room in &self.expiration_changed {
+            term.write_line(&format!(
+                "  ! expiration: {} ({}): {} → {}",
+                room.path,
+                room.id,
+                format_period(room.from),
+                format_period(room.to)
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn format_period(period: Option<u64>) -> String {
+    match period {
+        Some(0) => "none".to_string(),
+        Some(seconds) => format!("{seconds}s"),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Exports the room tree below the given rooms (or all managed rooms) incl. expiration policies
+#[instrument(skip(term, auth, credentials))]
+pub async fn handle_baseline(
+    term: Term,
+    base_url: String,
+    rooms: Vec<RoomRef>,
+    all_managed: bool,
+    output: &Path,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = async {
+        let parent_ids = if all_managed {
+            None
+        } else {
+            let mut parent_ids = vec![];
+            for room in &rooms {
+                parent_ids.push(resolve_room(&dracoon, room).await?);
+            }
+            Some(parent_ids)
+        };
+
+        let baseline = Baseline {
+            version: BASELINE_VERSION,
+            tenant: dracoon.get_base_url().to_string(),
+            created_at: Utc::now(),
+            rooms: snapshot_rooms(&dracoon, parent_ids.as_deref()).await?,
+            parent_ids,
+        };
+
+        let content = serde_json::to_vec_pretty(&baseline)
+            .map_err(|e| AppError::io_error(format!("Failed to serialize baseline: {e}")))?;
+        fs::write(output, content)?;
+
+        info!(
+            "Exported baseline with {} rooms to {}",
+            baseline.rooms.len(),
+            output.display()
+        );
+        term.write_line(&format!(
+            "► Exported {} rooms to {}",
+            baseline.rooms.len(),
+            output.display()
+        ))?;
+
+        Ok(())
+    }
+    .await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+/// Compares the current room tree with a baseline - returns `DriftDetected` if anything changed
+#[instrument(skip(term, auth, credentials))]
+pub async fn handle_diff(
+    term: Term,
+    base_url: String,
+    baseline: &Path,
+    format: OutputFormat,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let content = fs::read(baseline)?;
+    let baseline: Baseline = serde_json::from_slice(&content)
+        .map_err(|e| AppError::InvalidArgument(format!("Invalid baseline file: {e}")))?;
+
+    if baseline.version != BASELINE_VERSION {
+        return Err(AppError::InvalidArgument(format!(
+            "Unsupported baseline version: {}",
+            baseline.version
+        )));
+    }
+
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = async {
+        let tenant = dracoon.get_base_url().to_string();
+        if baseline.tenant != tenant {
+            return Err(AppError::InvalidArgument(format!(
+                "Baseline was exported from {} - not {tenant}.",
+                baseline.tenant
+            )));
+        }
+
+        let current = snapshot_rooms(&dracoon, baseline.parent_ids.as_deref()).await?;
+        let report = diff_rooms(&baseline.rooms, &current);
+
+        match format {
+            OutputFormat::Text => {
+                term.write_line(&format!(
+                    "► Baseline from {}, {} rooms (now {})",
+                    baseline.created_at.to_rfc3339(),
+                    baseline.rooms.len(),
+                    current.len()
+                ))?;
+                report.print(&term)?;
+            }
+            OutputFormat::Json => {
+                let json = serde_json::to_string_pretty(&report)
+                    .map_err(|e| AppError::io_error(format!("Failed to serialize report: {e}")))?;
+                term.write_line(&json)?;
+            }
+        }
+
+        match report.count() {
+            0 => Ok(()),
+            changes => Err(AppError::DriftDetected(changes)),
+        }
+    }
+    .await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+async fn snapshot_rooms(
+    dracoon: &Dracoon<Connected>,
+    parent_ids: Option<&[u64]>,
+) -> Result<Vec<RoomSnapshot>, AppError> {
+    let rooms = match parent_ids {
+        Some(parent_ids) => collect_rooms(dracoon.clone(), parent_ids).await?,
+        None => collect_managed_rooms(dracoon.clone()).await?,
+    };
+
+    let policies = get_room_policies(dracoon, rooms).await;
+    let total = policies.len();
+
+    let mut snapshots = vec![];
+    let mut failed = 0;
+    for (room, policies) in policies {
+        match expiration_period(policies) {
+            Ok(expiration_period) => snapshots.push(RoomSnapshot {
+                id: room.id,
+                path: node_path(&room),
+                expiration_period,
+            }),
+            Err(e) => {
+                error!("Failed to fetch policies of room {}: {}", room.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    // an incomplete snapshot would be reported as drift
+    if failed > 0 {
+        return Err(AppError::PolicyFetchFailed { failed, total });
+    }
+
+    // stable file content for review in version control
+    snapshots.sort_by_key(|room| room.id);
+
+    Ok(snapshots)
+}
+
+// missing permission is part of the snapshot - any other error (timeout, 5xx) is not
+fn expiration_period(
+    policies: Result<RoomPolicies, DracoonClientError>,
+) -> Result<Option<u64>, DracoonClientError> {
+    match policies {
+        Ok(policies) => Ok(Some(policies.default_expiration_period)),
+        Err(DracoonClientError::Http(e)) if e.is_forbidden() => {
+            warn!("Missing permission to read room policies: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// rooms are matched by id - a changed path is a rename (or move)
+fn diff_rooms(baseline: &[RoomSnapshot], current: &[RoomSnapshot]) -> DriftReport {
+    let mut baseline = baseline
+        .iter()
+        .map(|room| (room.id, room))
+        .collect::<BTreeMap<_, _>>();
+    let mut report = DriftReport::default();
+
+    for room in current {
+        let Some(previous) = baseline.remove(&room.id) else {
+            report.added.push(room.clone());
+            continue;
+        };
+
+        if previous.path != room.path {
+            report.renamed.push(Renamed {
+                id: room.id,
+                from: previous.path.clone(),
+                to: room.path.clone(),
+            });
+        }
+
+        if previous.expiration_period != room.expiration_period {
+            report.expiration_changed.push(ExpirationChanged {
+                id: room.id,
+                path: room.path.clone(),
+                from: previous.expiration_period,
+                to: room.expiration_period,
+            });
+        }
+    }
+
+    report.removed = baseline.into_values().cloned().collect();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(id: u64, path: &str, expiration_period: u64) -> RoomSnapshot {
+        RoomSnapshot {
+            id,
+            path: path.to_string(),
+            expiration_period: Some(expiration_period),
+        }
+    }
+
+    #[test]
+    fn test_diff_rooms() {
+        let baseline = vec![
+            room(1, "/Clients/ACME", 0),
+            room(2, "/Clients/Old", 0),
+            room(3, "/Clients/Foo", 0),
+        ];
+        let current = vec![
+            room(1, "/Clients/ACME", 2_592_000),
+            room(3, "/Clients/Bar", 0),
+            room(4, "/Clients/New", 0),
+        ];
+
+        let report = diff_rooms(&baseline, &current);
+
+        assert_eq!(report.added, vec![room(4, "/Clients/New", 0)]);
+        assert_eq!(report.removed, vec![room(2, "/Clients/Old", 0)]);
+        assert_eq!(
+            report.renamed,
+            vec![Renamed {
+                id: 3,
+                from: "/Clients/Foo".to_string(),
+                to: "/Clients/Bar".to_string()
+            }]
+        );
+        assert_eq!(
+            report.expiration_changed,
+            vec![ExpirationChanged {
+                id: 1,
+                path: "/Clients/ACME".to_string(),
+                from: Some(0),
+                to: Some(2_592_000)
+            }]
+        );
+        assert_eq!(report.count(), 4);
+    }
+
+    #[test]
+    fn test_expiration_period() {
+        use dco3::auth::models::DracoonErrorResponse;
+
+        let policies = RoomPolicies {
+            default_expiration_period: 30,
+            is_virus_protection_enabled: false,
+        };
+        let forbidden = DracoonErrorResponse::new(403, "Forbidden");
+        let server_error = DracoonErrorResponse::new(503, "Service Unavailable");
+
+        assert_eq!(expiration_period(Ok(policies)).ok(), Some(Some(30)));
+        assert_eq!(
+            expiration_period(Err(DracoonClientError::Http(forbidden))).ok(),
+            Some(None)
+        );
+        assert!(expiration_period(Err(DracoonClientError::Http(server_error))).is_err());
+        assert!(
+            expiration_period(Err(DracoonClientError::ConnectionFailed("timeout".into()))).is_err()
+        );
+    }
+
+    #[test]
+    fn test_diff_rooms_no_drift() {
+        let rooms = vec![room(1, "/Clients/ACME", 0)];
+        assert_eq!(diff_rooms(&rooms, &rooms), DriftReport::default());
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use console::Term;
+use dco3::{auth::Connected, nodes::Node, Dracoon};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use tracing::{debug, info, instrument};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{CredentialOptions, ExpiringOptions, PasswordAuth, ReportFormat},
+    utils::{
+        dates::to_local_string,
+        strings::{to_csv_field, to_readable_size},
+        table::{format_table, Align},
+    },
+};
+
+use super::{
+    collect_rooms, get_parent_ids,
+    managed::collect_managed_rooms,
+    nodes::{node_path, search_file_pages},
+};
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ExpiringFile {
+    id: u64,
+    path: String,
+    size: Option<u64>,
+    expire_at: DateTime<Utc>,
+    room_id: u64,
+    room: String,
+}
+
+// expiration window [from, until)
+struct Window {
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+}
+
+/// Lists files below the collected rooms whose expiration date is within the given window
+#[instrument(name = "expiring", skip(term, auth, credentials))]
+pub async fn handle_expiring(
+    term: Term,
+    base_url: String,
+    opts: ExpiringOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = async {
+        let rooms = if opts.all_managed {
+            collect_managed_rooms(dracoon.clone()).await?
+        } else {
+            let parent_ids = get_parent_ids(&dracoon, &term, &opts.rooms).await?;
+            collect_rooms(dracoon.clone(), &parent_ids).await?
+        };
+
+        let now = Utc::now();
+        let window = Window {
+            from: now,
+            until: now + opts.within,
+        };
+
+        let files = find_expiring_files(&dracoon, &rooms, &window).await?;
+        info!(
+            "Found {} files expiring until {}",
+            files.len(),
+            window.until.to_rfc3339()
+        );
+
+        match opts.format {
+            ReportFormat::Table => print_table(&term, &files, &window),
+            ReportFormat::Csv => print_csv(&term, &files),
+            ReportFormat::Json => {
+                let json = serde_json::to_string_pretty(&files)
+                    .map_err(|e| AppError::io_error(format!("Failed to serialize report: {e}")))?;
+                Ok(term.write_line(&json)?)
+            }
+        }
+    }
+    .await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+// searches each subtree once - starting at the rooms whose parent was not collected
+async fn find_expiring_files(
+    dracoon: &Dracoon<Connected>,
+    rooms: &[Node],
+    window: &Window,
+) -> Result<Vec<ExpiringFile>, AppError> {
+    let room_ids = rooms.iter().map(|room| room.id).collect::<HashSet<_>>();
+    let room_paths = rooms
+        .iter()
+        .map(|room| (room.id, format!("{}/", node_path(room))))
+        .collect::<HashMap<_, _>>();
+
+    let roots = rooms
+        .iter()
+        .filter(|room| !room.parent_id.is_some_and(|id| room_ids.contains(&id)))
+        .map(|room| room.id)
+        .collect::<Vec<_>>();
+    debug!("Searching files below {} rooms", roots.len());
+
+    let room_paths = &room_paths;
+    let mut searches = stream::iter(roots)
+        .map(|root_id| {
+            search_file_pages(dracoon, root_id, window.from, window.until).try_fold(
+                vec![],
+                move |mut files, page| async move {
+                    files.extend(
+                        page.into_iter()
+                            .filter_map(|file| to_expiring_file(file, room_paths, window)),
+                    );
+                    Ok(files)
+                },
+            )
+        })
+        .buffer_unordered(10);
+
+    let mut files = vec![];
+    while let Some(result) = searches.next().await {
+        files.extend(result?);
+    }
+
+    files.sort_by(|a, b| a.expire_at.cmp(&b.expire_at).then(a.path.cmp(&b.path)));
+
+    Ok(files)
+}
+
+// files in rooms that were not collected (e.g. deeper sub rooms) are skipped
+fn to_expiring_file(
+    file: Node,
+    room_paths: &HashMap<u64, String>,
+    window: &Window,
+) -> Option<ExpiringFile> {
+    let expire_at = file
+        .expire_at
+        .filter(|expire_at| *expire_at >= window.from && *expire_at < window.until)?;
+
+    let path = node_path(&file);
+
+    // the containing room - or the deepest collected room the path is in
+    let (room_id, room_path) = match file.auth_parent_id {
+        Some(room_id) => room_paths.get_key_value(&room_id)?,
+        None => room_paths
+            .iter()
+            .filter(|(_, room_path)| path.starts_with(room_path.as_str()))
+            .max_by_key(|(_, room_path)| room_path.len())?,
+    };
+
+    Some(ExpiringFile {
+        id: file.id,
+        path,
+        size: file.size,
+        expire_at,
+        room_id: *room_id,
+        room: room_path.trim_end_matches('/').to_string(),
+    })
+}
+
+fn print_table(term: &Term, files: &[ExpiringFile], window: &Window) -> Result<(), AppError> {
+    term.write_line(&format!(
+        "► {} files expire until {}",
+        files.len(),
+        to_local_string(&window.until)
+    ))?;
+
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let rows = files
+        .iter()
+        .map(|file| {
+            [
+                to_local_string(&file.expire_at),
+                file.size.map_or_else(String::new, to_readable_size),
+                file.room.clone(),
+                file.path.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let lines = format_table(
+        ["EXPIRES", "SIZE", "ROOM", "PATH"],
+        [Align::Left, Align::Right, Align::Left, Align::Left],
+        &rows,
+    );
+    for line in lines {
+        term.write_line(&line)?;
+    }
+
+    Ok(())
+}
+
+fn print_csv(term: &Term, files: &[ExpiringFile]) -> Result<(), AppError> {
+    term.write_line("path,size,expire_at,room_id,room")?;
+
+    for file in files {
+        term.write_line(&format!(
+            "{},{},{},{},{}",
+            to_csv_field(&file.path),
+            file.size.map_or_else(String::new, |size| size.to_string()),
+            file.expire_at.to_rfc3339(),
+            file.room_id,
+            to_csv_field(&file.room)
+        ))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::from_json;
+
+    fn file(id: u64, parent_path: &str, expire_at: &str, auth_parent_id: Option<u64>) -> Node {
+        from_json(serde_json::json!({
+            "id": id, "type": "file", "name": format!("file-{id}.pdf"), "size": 1024,
+            "parentPath": parent_path, "expireAt": expire_at, "authParentId": auth_parent_id
+        }))
+    }
+
+    #[test]
+    fn test_to_expiring_file() {
+        let window = Window {
+            from: "2024-05-01T00:00:00Z".parse().unwrap(),
+            until: "2024-05-15T00:00:00Z".parse().unwrap(),
+        };
+        let room_paths = HashMap::from([
+            (10, "/Clients/".to_string()),
+            (11, "/Clients/ACME/".to_string()),
+        ]);
+
+        let expiring = to_expiring_file(
+            file(1, "/Clients/ACME/", "2024-05-10T00:00:00Z", Some(11)),
+            &room_paths,
+            &window,
+        )
+        .unwrap();
+        assert_eq!(expiring.path, "/Clients/ACME/file-1.pdf");
+        assert_eq!(expiring.room, "/Clients/ACME");
+
+        // no containing room: deepest collected room on the path
+        let expiring = to_expiring_file(
+            file(2, "/Clients/ACME/Docs/", "2024-05-10T00:00:00Z", None),
+            &room_paths,
+            &window,
+        )
+        .unwrap();
+        assert_eq!(expiring.room_id, 11);
+
+        // outside of the window
+        assert!(to_expiring_file(
+            file(3, "/Clients/ACME/", "2024-05-20T00:00:00Z", Some(11)),
+            &room_paths,
+            &window,
+        )
+        .is_none());
+
+        // room was not collected
+        assert!(to_expiring_file(
+            file(4, "/Clients/ACME/Sub/", "2024-05-10T00:00:00Z", Some(12)),
+            &room_paths,
+            &window,
+        )
+        .is_none());
+    }
+}
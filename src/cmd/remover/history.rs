@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use console::Term;
+use dco3::{
+    auth::Connected,
+    eventlog::{EventStatus, EventlogParams, EventlogSortBy, LogEvent, LogOperation},
+    Dracoon, Eventlog, SortOrder,
+};
+use serde::Serialize;
+use tracing::{info, instrument};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{CredentialOptions, HistoryOptions, PasswordAuth, ReportFormat},
+    utils::{
+        dates::to_local_string,
+        strings::to_csv_field,
+        table::{format_table, Align},
+    },
+};
+
+use super::{
+    collect_rooms, get_all, get_parent_ids,
+    managed::collect_managed_rooms,
+    nodes::node_path,
+    roles::{require_roles, LOG_AUDITOR},
+    LIST_PAGE_SIZE,
+};
+
+// default date range if --from is omitted
+const DEFAULT_RANGE_DAYS: i64 = 30;
+
+// names of the event log operations that change room policies (e.g. the default expiration period)
+const POLICY_OPERATIONS: &[&str] = &["Change room policies"];
+
+// the given operation ids - or the known policy operations if no ids are given.
+// Operation names differ between DRACOON versions: fails if nothing matches instead of
+// reporting no changes
+fn policy_operations(
+    operations: Vec<LogOperation>,
+    ids: &[i64],
+) -> Result<Vec<LogOperation>, AppError> {
+    if !ids.is_empty() {
+        let unknown = ids
+            .iter()
+            .filter(|id| !operations.iter().any(|operation| operation.id == **id))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(AppError::InvalidArgument(format!(
+                "Unknown event log operation ids: {}",
+                unknown.join(", ")
+            )));
+        }
+
+        return Ok(operations
+            .into_iter()
+            .filter(|operation| ids.contains(&operation.id))
+            .collect());
+    }
+
+    let (selected, other): (Vec<_>, Vec<_>) = operations.into_iter().partition(|operation| {
+        !operation.is_deprecated
+            && POLICY_OPERATIONS
+                .iter()
+                .any(|name| operation.name.eq_ignore_ascii_case(name))
+    });
+    if selected.is_empty() {
+        let candidates = other
+            .iter()
+            .filter(|operation| operation.name.to_lowercase().contains("polic"))
+            .map(|operation| format!("{} ({})", operation.id, operation.name))
+            .collect::<Vec<_>>();
+        return Err(AppError::InvalidArgument(format!(
+            "No event log operation named {} - select the operations with --operation <ID>{}",
+            POLICY_OPERATIONS.join(", "),
+            if candidates.is_empty() {
+                String::new()
+            } else {
+                format!(" (candidates: {})", candidates.join(", "))
+            }
+        )));
+    }
+
+    Ok(selected)
+}
+
+// policy change in one of the collected rooms - the event log records no old and new policy
+// values: the event attributes are reported as returned by DRACOON, their meaning depends on
+// the operation
+#[derive(Debug, PartialEq, Serialize)]
+struct PolicyChange {
+    time: DateTime<Utc>,
+    user_id: i64,
+    user: String,
+    room_id: u64,
+    room: String,
+    operation: String,
+    attribute1: Option<String>,
+    attribute2: Option<String>,
+    message: String,
+}
+
+// events on rooms that were not collected are skipped
+fn to_policy_change(event: LogEvent, room_paths: &HashMap<u64, String>) -> Option<PolicyChange> {
+    let room_id = event.object_id1.and_then(|id| u64::try_from(id).ok())?;
+    let room = room_paths.get(&room_id)?;
+
+    Some(PolicyChange {
+        time: event.time,
+        user_id: event.user_id,
+        user: event.user_name.unwrap_or_else(|| event.user_id.to_string()),
+        room_id,
+        room: room.clone(),
+        operation: event.operation_name.unwrap_or_default(),
+        attribute1: event.attribute1,
+        attribute2: event.attribute2,
+        message: event.message,
+    })
+}
+
+/// Lists the policy changes in the collected rooms from the event log
+#[instrument(name = "history", skip(term, auth, credentials))]
+pub async fn handle_history(
+    term: Term,
+    base_url: String,
+    opts: HistoryOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = history(&term, &dracoon, opts).await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+async fn history(
+    term: &Term,
+    dracoon: &Dracoon<Connected>,
+    opts: HistoryOptions,
+) -> Result<(), AppError> {
+    let to = opts.to.unwrap_or_else(Utc::now);
+    let from = opts
+        .from
+        .unwrap_or(to - TimeDelta::days(DEFAULT_RANGE_DAYS));
+    if from >= to {
+        return Err(AppError::InvalidArgument(
+            "--from must be before --to".to_string(),
+        ));
+    }
+
+    require_roles(dracoon, &[LOG_AUDITOR]).await?;
+
+    let rooms = if opts.all_managed {
+        collect_managed_rooms(dracoon.clone()).await?
+    } else {
+        let parent_ids = get_parent_ids(dracoon, term, &opts.rooms).await?;
+        collect_rooms(dracoon.clone(), &parent_ids).await?
+    };
+    let room_paths = rooms
+        .iter()
+        .map(|room| (room.id, node_path(room)))
+        .collect::<HashMap<_, _>>();
+
+    let operations = policy_operations(
+        dracoon
+            .eventlog()
+            .get_event_operations()
+            .await?
+            .operation_list,
+        &opts.operations,
+    )?;
+
+    let mut changes = vec![];
+    for operation in &operations {
+        let events = get_all(|offset| async move {
+            dracoon
+                .eventlog()
+                .get_events(
+                    EventlogParams::builder()
+                        .with_offset(offset)
+                        .with_limit(LIST_PAGE_SIZE)
+                        .with_sort(EventlogSortBy::Time(SortOrder::Asc))
+                        .with_operation_type(operation.id)
+                        .with_status(EventStatus::Success)
+                        .with_date_start(from)
+                        .with_date_end(to)
+                        .build(),
+                )
+                .await
+        })
+        .await?;
+
+        info!(
+            "Found {} events for operation {} ({})",
+            events.len(),
+            operation.name,
+            operation.id
+        );
+        changes.extend(
+            events
+                .into_iter()
+                .filter_map(|event| to_policy_change(event, &room_paths)),
+        );
+    }
+
+    changes.sort_by(|a, b| a.time.cmp(&b.time).then(a.room.cmp(&b.room)));
+    info!(
+        "Found {} policy changes in {} rooms",
+        changes.len(),
+        rooms.len()
+    );
+
+    match opts.format {
+        ReportFormat::Table => print_table(term, &changes, from, to),
+        ReportFormat::Csv => print_csv(term, &changes),
+        ReportFormat::Json => {
+            let json = serde_json::to_string_pretty(&changes)
+                .map_err(|e| AppError::io_error(format!("Failed to serialize report: {e}")))?;
+            Ok(term.write_line(&json)?)
+        }
+    }
+}
+
+fn print_table(
+    term: &Term,
+    changes: &[PolicyChange],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<(), AppError> {
+    term.write_line(&format!(
+        "► {} policy changes between {} and {}",
+        changes.len(),
+        to_local_string(&from),
+        to_local_string(&to)
+    ))?;
+
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let rows = changes
+        .iter()
+        .map(|change| {
+            [
+                to_local_string(&change.time),
+                change.user.clone(),
+                change.room.clone(),
+                change.operation.clone(),
+                change.attribute1.clone().unwrap_or_else(|| "-".to_string()),
+                change.attribute2.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let lines = format_table(
+        [
+            "TIME",
+            "USER",
+            "ROOM",
+            "OPERATION",
+            "ATTRIBUTE 1",
+            "ATTRIBUTE 2",
+        ],
+        [Align::Left; 6],
+        &rows,
+    );
+    for line in lines {
+        term.write_line(&line)?;
+    }
+
+    Ok(())
+}
+
+fn print_csv(term: &Term, changes: &[PolicyChange]) -> Result<(), AppError> {
+    term.write_line("time,user_id,user,room_id,room,operation,attribute1,attribute2,message")?;
+
+    for change in changes {
+        term.write_line(&format!(
+            "{},{},{},{},{},{},{},{},{}",
+            change.time.to_rfc3339(),
+            change.user_id,
+            to_csv_field(&change.user),
+            change.room_id,
+            to_csv_field(&change.room),
+            to_csv_field(&change.operation),
+            to_csv_field(change.attribute1.as_deref().unwrap_or_default()),
+            to_csv_field(change.attribute2.as_deref().unwrap_or_default()),
+            to_csv_field(&change.message)
+        ))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::from_json;
+
+    fn event(room_id: Option<i64>, user_name: Option<&str>) -> LogEvent {
+        from_json(serde_json::json!({
+            "id": 1, "time": "2024-05-10T08:00:00Z", "userId": 7,
+            "message": "Room policies changed", "operationName": "Change room policies",
+            "userName": user_name, "objectId1": room_id,
+            "attribute1": "0", "attribute2": "30"
+        }))
+    }
+
+    #[test]
+    fn test_policy_operations() {
+        let operations = from_json::<Vec<LogOperation>>(serde_json::json!([
+            { "id": 1, "name": "Change room policies", "isDeprecated": false },
+            { "id": 2, "name": "Upload file", "isDeprecated": false },
+            { "id": 3, "name": "Change room policies", "isDeprecated": true },
+            { "id": 4, "name": "Change room policies and quota", "isDeprecated": false }
+        ]));
+
+        let ids = |selected: &[i64]| {
+            policy_operations(operations.clone(), selected)
+                .unwrap()
+                .into_iter()
+                .map(|operation| operation.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&[]), vec![1]);
+
+        // explicitly selected operations
+        assert_eq!(ids(&[2, 3]), vec![2, 3]);
+        assert!(matches!(
+            policy_operations(operations.clone(), &[2, 9]),
+            Err(AppError::InvalidArgument(msg)) if msg.ends_with(": 9")
+        ));
+    }
+
+    #[test]
+    fn test_policy_operations_without_match() {
+        let operations = from_json::<Vec<LogOperation>>(serde_json::json!([
+            { "id": 2, "name": "Upload file", "isDeprecated": false },
+            { "id": 5, "name": "Room policies changed", "isDeprecated": false }
+        ]));
+
+        let res = policy_operations(operations, &[]);
+
+        assert!(matches!(
+            res,
+            Err(AppError::InvalidArgument(msg))
+                if msg.contains("--operation <ID>") && msg.contains("5 (Room policies changed)")
+        ));
+    }
+
+    #[test]
+    fn test_to_policy_change() {
+        let room_paths = HashMap::from([(11, "/Clients/ACME".to_string())]);
+
+        let change = to_policy_change(event(Some(11), Some("jdoe")), &room_paths).unwrap();
+        assert_eq!(change.room, "/Clients/ACME");
+        assert_eq!(change.user, "jdoe");
+        assert_eq!(change.attribute1.as_deref(), Some("0"));
+        assert_eq!(change.attribute2.as_deref(), Some("30"));
+
+        // falls back to the user id
+        let change = to_policy_change(event(Some(11), None), &room_paths).unwrap();
+        assert_eq!(change.user, "7");
+
+        // room was not collected
+        assert!(to_policy_change(event(Some(12), Some("jdoe")), &room_paths).is_none());
+        assert!(to_policy_change(event(None, Some("jdoe")), &room_paths).is_none());
+    }
+}
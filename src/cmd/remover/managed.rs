@@ -0,0 +1,66 @@
+use dco3::{auth::Connected, nodes::Node, Dracoon};
+use futures::StreamExt;
+use tracing::{debug, instrument, warn};
+
+use crate::cmd::errors::AppError;
+
+use super::{
+    nodes::{get_all_nodes, search_rooms, SEARCH_DEPTH_ALL},
+    permissions::has_manage_permission,
+};
+
+/// Collects every room the current user holds manage permission on via node search -
+/// falls back to a traversal of the room hierarchy if search fails.
+#[instrument(skip(dracoon))]
+pub async fn collect_managed_rooms(dracoon: Dracoon<Connected>) -> Result<Vec<Node>, AppError> {
+    match search_rooms(&dracoon, None, SEARCH_DEPTH_ALL).await {
+        // without permissions every room would be dropped
+        Ok(rooms) if rooms.iter().all(|room| room.permissions.is_some()) => {
+            return Ok(rooms.into_iter().filter(has_manage_permission).collect());
+        }
+        Ok(_) => warn!("Room search returned no permissions - falling back to traversal"),
+        Err(e) => warn!("Room search failed - falling back to traversal: {}", e),
+    }
+
+    traverse_managed_rooms(dracoon).await
+}
+
+#[instrument(skip(dracoon))]
+pub(super) async fn traverse_managed_rooms(
+    dracoon: Dracoon<Connected>,
+) -> Result<Vec<Node>, AppError> {
+    let mut managed = vec![];
+    let mut level = get_all_nodes(dracoon.clone(), None).await?;
+    let mut depth = 0;
+
+    while !level.is_empty() {
+        // only descend into rooms that (may) contain sub rooms
+        let parent_ids = level
+            .iter()
+            .filter(|room| room.cnt_rooms != Some(0))
+            .map(|room| room.id)
+            .collect::<Vec<_>>();
+
+        managed.extend(level.into_iter().filter(has_manage_permission));
+
+        let fetch_futures = parent_ids
+            .into_iter()
+            .map(|parent_id| get_all_nodes(dracoon.clone(), Some(parent_id)));
+        let mut fetch_stream = futures::stream::iter(fetch_futures).buffer_unordered(10);
+
+        let mut next_level = vec![];
+        while let Some(result) = fetch_stream.next().await {
+            next_level.extend(result?);
+        }
+
+        depth += 1;
+        debug!(
+            "Scanned level {} - {} managed rooms so far",
+            depth,
+            managed.len()
+        );
+        level = next_level;
+    }
+
+    Ok(managed)
+}
@@ -1,41 +1,298 @@
+use confirm::{confirm_run, confirm_stream_run, print_dry_run, RunSummary};
+use std::{cell::Cell, collections::HashSet, future::Future};
+
+use chrono::{DateTime, Utc};
 use console::Term;
-use dco3::nodes::RoomPoliciesRequest;
-use dco3::Rooms;
-use dco3::{auth::Connected, Dracoon};
-use nodes::get_all_nodes;
-use tracing::info;
+use dco3::nodes::{Node, RoomPoliciesRequest};
+use dco3::{auth::Connected, Dracoon, RangedItems};
+use dco3::{Nodes, Rooms};
+use managed::collect_managed_rooms;
+use nodes::{get_all_nodes, search_rooms};
+use permissions::check_manage_permissions;
+use picker::pick_rooms;
+use pipeline::{stream_remove_expiration, RoomSource};
+use resolve::resolve_room;
+use serde::{ser::SerializeMap, Serialize};
+use tokio::time::Instant;
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument, Span};
 
+mod configure;
+mod confirm;
+mod drift;
+mod expiring;
+mod history;
+mod managed;
 mod nodes;
+mod permissions;
+mod picker;
+mod pipeline;
+mod recover;
+mod recycle_bin;
+mod resolve;
+mod roles;
+mod shares;
+mod users;
+mod watch;
+
+pub use configure::handle_configure;
+pub use drift::{handle_baseline, handle_diff};
+pub use expiring::handle_expiring;
+pub use history::handle_history;
+pub use recover::handle_recover;
+pub use shares::handle_shares;
+pub use users::handle_users;
+pub use watch::handle_watch;
 
-use super::{errors::AppError, init_dracoon, models::PasswordAuth};
+use super::{
+    errors::AppError,
+    init_dracoon,
+    models::{
+        CredentialOptions, ExpirationChange, NotifyOptions, OutputFormat, PasswordAuth, RoomRef,
+        RunOptions, UpdateCount,
+    },
+    notify::{send_notifications, RunReport},
+};
 
-use futures::stream::StreamExt; // for handling concurrent streams // for join_all
+use futures::stream::{Stream, StreamExt}; // for handling concurrent streams // for join_all
 
+#[instrument(name = "run", skip(term, auth, credentials, notify))]
 pub async fn handle_remove_expiration(
-    _term: Term,
+    term: Term,
     base_url: String,
-    data_room_id: u64,
+    opts: RunOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+    notify: NotifyOptions,
+) -> Result<(), AppError> {
+    let start = Instant::now();
+    let mut count = UpdateCount::default();
+    let res = remove_expiration(
+        &term,
+        &base_url,
+        opts,
+        auth,
+        credentials.clone(),
+        &mut count,
+    )
+    .await;
+
+    let report = RunReport::new(&base_url, count, &res, start.elapsed());
+    send_notifications(&term, &notify, &credentials, &report).await;
+
+    res
+}
+
+// `count` holds the updated and failed rooms - also if the run fails after the first update
+async fn remove_expiration(
+    term: &Term,
+    base_url: &str,
+    opts: RunOptions,
     auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+    count: &mut UpdateCount,
 ) -> Result<(), AppError> {
-    let dracoon: Dracoon<Connected> = init_dracoon(&base_url, auth.clone(), true).await?;
+    let session = init_dracoon(base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = async {
+        let parent_ids = if opts.all_managed {
+            None
+        } else {
+            Some(get_parent_ids(&dracoon, term, &opts.data_room_ids).await?)
+        };
+
+        let parent = match &parent_ids {
+            None => "all rooms with manage permission".to_string(),
+            Some(parent_ids) => {
+                let mut parents = vec![];
+                for parent_id in parent_ids {
+                    let parent = dracoon.nodes().get_node(*parent_id).await?;
+                    parents.push(format!("{} ({})", parent.name, parent.id));
+                }
+                parents.join(", ")
+            }
+        };
+
+        let user = dracoon.get_user_info().await?;
+
+        let mut summary = RunSummary {
+            tenant: dracoon.get_base_url().to_string(),
+            user: format!(
+                "{} {} ({})",
+                user.first_name, user.last_name, user.user_name
+            ),
+            parent,
+            room_names: vec![],
+        };
+
+        // rooms are updated while they are discovered - unmanaged rooms are skipped (--skip-unmanaged)
+        if opts.stream {
+            if !confirm_stream_run(term, &summary, opts.yes)? {
+                return Err(AppError::Aborted("no rooms modified".to_string()));
+            }
+
+            let source = match parent_ids {
+                None => RoomSource::AllManaged,
+                Some(parent_ids) => RoomSource::Parents(parent_ids),
+            };
+
+            term.write_line("► Updating rooms while they are discovered...")?;
+            let (stream_count, res) = stream_remove_expiration(term, dracoon.clone(), source).await;
+            *count = stream_count;
+            return res;
+        }
 
-    let room_ids = collect_home_room_and_sub_room_ids(dracoon.clone(), data_room_id).await?;
+        let rooms = match &parent_ids {
+            None => collect_managed_rooms(dracoon.clone()).await?,
+            Some(parent_ids) => collect_rooms(dracoon.clone(), parent_ids).await?,
+        };
 
-    revert_expiration_date(dracoon, room_ids).await?;
+        let rooms = check_manage_permissions(term, rooms, opts.skip_unmanaged)?;
 
-    Ok(())
+        if rooms.is_empty() {
+            term.write_line("► No rooms to update.")?;
+            return Ok(());
+        }
+
+        summary.room_names = rooms.iter().map(|room| room.name.clone()).collect();
+
+        if opts.dry_run {
+            print_dry_run(term, &summary)?;
+            return Ok(());
+        }
+
+        if !confirm_run(term, &summary, opts.yes)? {
+            return Err(AppError::Aborted("no rooms modified".to_string()));
+        }
+
+        let room_ids = rooms.into_iter().map(|room| room.id).collect::<Vec<_>>();
+        let (run_count, res) = revert_expiration_date(dracoon, room_ids).await;
+        *count = run_count;
+
+        term.write_line(&format!("► Updated {} rooms.", run_count.updated))?;
+
+        res
+    }
+    .await;
+
+    session.persist_refresh_token().await;
+
+    res
 }
 
+// no room given: let the user pick parent rooms
+async fn get_parent_ids(
+    dracoon: &Dracoon<Connected>,
+    term: &Term,
+    rooms: &[RoomRef],
+) -> Result<Vec<u64>, AppError> {
+    if rooms.is_empty() {
+        return pick_rooms(dracoon, term).await;
+    }
+
+    let mut parent_ids = vec![];
+    for room in rooms {
+        let parent_id = resolve_room(dracoon, room).await?;
+
+        // the same room may be given by id and by path
+        if !parent_ids.contains(&parent_id) {
+            parent_ids.push(parent_id);
+        }
+    }
+
+    Ok(parent_ids)
+}
+
+// collects rooms below all parents - rooms below several parents are only included once
+async fn collect_rooms(
+    dracoon: Dracoon<Connected>,
+    parent_ids: &[u64],
+) -> Result<Vec<Node>, AppError> {
+    let mut seen = HashSet::new();
+    let mut rooms = vec![];
+    let mut duplicates = 0;
+
+    for parent_id in parent_ids {
+        let parent_rooms = collect_home_room_and_sub_room_ids(dracoon.clone(), *parent_id).await?;
+
+        for room in parent_rooms {
+            if seen.insert(room.id) {
+                rooms.push(room);
+            } else {
+                duplicates += 1;
+            }
+        }
+    }
+
+    if duplicates > 0 {
+        info!("Skipped {} rooms found below several parents", duplicates);
+    }
+
+    Ok(rooms)
+}
+
+// children and grand children of the parent
+const SEARCH_DEPTH_SUB_ROOMS: i8 = 1;
+// page size of the list endpoints (shares, users, groups, room members)
+const LIST_PAGE_SIZE: u64 = 500;
+
+/// Collects the rooms below the parent and their sub rooms via node search (a few paginated
+/// requests for the whole subtree) - falls back to one listing per room if search fails.
+#[instrument(skip(dracoon))]
 async fn collect_home_room_and_sub_room_ids(
     dracoon: Dracoon<Connected>,
     data_room_id: u64,
-) -> Result<Vec<u64>, AppError> {
-    let mut room_ids = vec![];
+) -> Result<Vec<Node>, AppError> {
+    match search_rooms(&dracoon, Some(data_room_id), SEARCH_DEPTH_SUB_ROOMS).await {
+        // permissions are required for the manage permission check
+        Ok(rooms) if rooms.iter().all(|room| room.permissions.is_some()) => {
+            return Ok(rebuild_sub_room_tree(data_room_id, rooms));
+        }
+        Ok(_) => warn!("Room search returned no permissions - falling back to listing"),
+        Err(e) => warn!("Room search failed - falling back to listing: {}", e),
+    }
+
+    list_home_room_and_sub_room_ids(dracoon, data_room_id).await
+}
+
+// keeps rooms whose parent is the data room or one of its rooms (home rooms first)
+fn rebuild_sub_room_tree(data_room_id: u64, rooms: Vec<Node>) -> Vec<Node> {
+    let (home_rooms, other): (Vec<_>, Vec<_>) = rooms
+        .into_iter()
+        .partition(|room| room.parent_id == Some(data_room_id));
+
+    let home_room_ids = home_rooms
+        .iter()
+        .map(|room| room.id)
+        .collect::<HashSet<_>>();
+
+    let (sub_rooms, unrelated): (Vec<_>, Vec<_>) = other.into_iter().partition(|room| {
+        room.parent_id
+            .is_some_and(|parent_id| home_room_ids.contains(&parent_id))
+    });
+
+    if !unrelated.is_empty() {
+        debug!(
+            "Ignoring {} search results outside of room {}",
+            unrelated.len(),
+            data_room_id
+        );
+    }
+
+    home_rooms.into_iter().chain(sub_rooms).collect()
+}
+
+#[instrument(skip(dracoon))]
+async fn list_home_room_and_sub_room_ids(
+    dracoon: Dracoon<Connected>,
+    data_room_id: u64,
+) -> Result<Vec<Node>, AppError> {
+    let mut rooms = vec![];
 
     let home_rooms = get_all_nodes(dracoon.clone(), Some(data_room_id)).await?;
 
-    home_rooms.items.iter().for_each(|room| {
-        room_ids.push(room.id);
+    home_rooms.iter().for_each(|room| {
+        rooms.push(room.clone());
     });
 
     let fetch_futures = home_rooms.into_iter().map(|room| {
@@ -52,7 +309,7 @@ async fn collect_home_room_and_sub_room_ids(
         match result {
             Ok(sub_rooms) => {
                 for sub_room in sub_rooms {
-                    room_ids.push(sub_room.id);
+                    rooms.push(sub_room);
                 }
             }
             Err(e) => {
@@ -60,44 +317,409 @@ async fn collect_home_room_and_sub_room_ids(
             }
         }
     }
-    Ok(room_ids)
+    Ok(rooms)
 }
 
+#[instrument(skip_all, fields(rooms = room_ids.len()))]
 async fn revert_expiration_date(
     dracoon: Dracoon<Connected>,
     room_ids: Vec<u64>,
-) -> Result<(), AppError> {
-    let revert_futures = room_ids.into_iter().map(|room_id| {
+) -> (UpdateCount, Result<(), AppError>) {
+    revert_expiration_date_stream(dracoon, futures::stream::iter(room_ids)).await
+}
+
+// updates rooms as they arrive
+async fn revert_expiration_date_stream(
+    dracoon: Dracoon<Connected>,
+    room_ids: impl Stream<Item = u64>,
+) -> (UpdateCount, Result<(), AppError>) {
+    update_concurrently(room_ids, |room_id| {
         let dracoon_clone = dracoon.clone();
+        let span = info_span!(
+            "update_room_policy",
+            room_id,
+            operation = "remove_expiration"
+        );
         async move {
             let new_policy = RoomPoliciesRequest::builder()
                 .with_default_expiration_period(0)
                 .build();
 
-            dracoon_clone
+            let start = Instant::now();
+            let res = dracoon_clone
                 .nodes()
                 .update_room_policies(room_id, new_policy)
-                .await?;
+                .await;
+            let duration_ms = start.elapsed().as_millis();
+
+            match &res {
+                Ok(()) => info!(status = "success", duration_ms, "Room policy updated"),
+                Err(e) => error!(
+                    status = "failed",
+                    duration_ms, "Room policy update failed: {}", e
+                ),
+            }
+            res.map_err(|e| AppError::from(e).for_room(room_id))?;
 
-            Ok(((), room_id))
+            info!(
+                "Expiration date reverted successfully for room with id {}",
+                room_id
+            );
+            Ok(())
         }
-    });
+        .instrument(span)
+    })
+    .await
+}
 
-    let mut revert_stream = futures::stream::iter(revert_futures).buffer_unordered(10);
+/// Runs the update for each item (10 concurrent requests) - returns the number of updated and failed items.
+/// Failures do not abort the other updates: `PartialSuccess` if some failed, the first error if all failed.
+async fn update_concurrently<T, F, Fut>(
+    items: impl Stream<Item = T>,
+    update: F,
+) -> (UpdateCount, Result<(), AppError>)
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<(), AppError>>,
+{
+    let update_stream = items.map(update).buffer_unordered(10);
+    let mut update_stream = std::pin::pin!(update_stream);
 
-    while let Some(result) = revert_stream.next().await {
-        match result {
-            Ok((_, room_id)) => {
-                info!(
-                    "Expiration date reverted successfully for room with id {}",
-                    room_id
-                );
+    let mut total = 0;
+    let mut failed = 0;
+    let mut first_error = None;
+
+    while let Some(result) = update_stream.next().await {
+        total += 1;
+
+        if let Err(e) = result {
+            failed += 1;
+            first_error.get_or_insert(e);
+        }
+    }
+
+    let count = UpdateCount {
+        updated: total - failed,
+        failed,
+    };
+
+    let res = match first_error {
+        None => Ok(()),
+        // nothing updated: report the actual failure class
+        Some(e) if failed == total => Err(e),
+        Some(_) => Err(AppError::PartialSuccess { failed, total }),
+    };
+
+    (count, res)
+}
+
+/// Expiration change of a bulk update for items expiring before `expires_before` (if set)
+#[derive(Debug, Clone, Copy)]
+struct ExpirationUpdate {
+    change: ExpirationChange,
+    expires_before: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+}
+
+impl ExpirationUpdate {
+    /// Current and new expiration (`None` removes it) of an item - items without expiration are
+    /// skipped, as are items expiring later and items the change would not modify
+    fn select(
+        &self,
+        expire_at: Option<DateTime<Utc>>,
+    ) -> Option<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+        let expire_at = expire_at?;
+
+        if self
+            .expires_before
+            .is_some_and(|before| expire_at >= before)
+        {
+            return None;
+        }
+
+        let new_expire_at = self.change.apply(expire_at, self.now);
+        (new_expire_at != Some(expire_at)).then_some((expire_at, new_expire_at))
+    }
+}
+
+/// Planned changes of a bulk update and how many of them were applied.
+/// The changes are serialized under the key `noun` (e.g. `shares`).
+#[derive(Debug)]
+struct BulkReport<T> {
+    noun: &'static str,
+    dry_run: bool,
+    changes: Vec<T>,
+    // set while the changes are borrowed by the updates
+    updated: Cell<usize>,
+    failed: Cell<usize>,
+}
+
+impl<T> BulkReport<T> {
+    fn new(noun: &'static str, dry_run: bool, changes: Vec<T>) -> Self {
+        Self {
+            noun,
+            dry_run,
+            changes,
+            updated: Cell::new(0),
+            failed: Cell::new(0),
+        }
+    }
+
+    /// Updates the changes concurrently, prints the report and returns the outcome of `update_concurrently`
+    async fn apply<'a, F, Fut>(
+        &'a self,
+        term: &Term,
+        format: OutputFormat,
+        update: F,
+    ) -> Result<(), AppError>
+    where
+        T: Serialize,
+        F: Fn(&'a T) -> Fut,
+        Fut: Future<Output = Result<(), AppError>>,
+    {
+        let (count, res) = update_concurrently(futures::stream::iter(&self.changes), update).await;
+        self.updated.set(count.updated);
+        self.failed.set(count.failed);
+
+        self.print(term, format)?;
+
+        res
+    }
+}
+
+impl<T: Serialize> BulkReport<T> {
+    fn print(&self, term: &Term, format: OutputFormat) -> Result<(), AppError> {
+        match format {
+            OutputFormat::Text if self.dry_run => {
+                term.write_line(&format!(
+                    "► Dry run - {} {} not modified.",
+                    self.changes.len(),
+                    self.noun
+                ))?;
             }
-            Err(e) => {
-                return Err(e);
+            OutputFormat::Text => {
+                term.write_line(&format!(
+                    "► Updated {} of {} {} ({} failed).",
+                    self.updated.get(),
+                    self.changes.len(),
+                    self.noun,
+                    self.failed.get()
+                ))?;
+            }
+            OutputFormat::Json => {
+                let json = serde_json::to_string_pretty(self)
+                    .map_err(|e| AppError::io_error(format!("Failed to serialize report: {e}")))?;
+                term.write_line(&json)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Serialize> Serialize for BulkReport<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("dry_run", &self.dry_run)?;
+        map.serialize_entry(self.noun, &self.changes)?;
+        map.serialize_entry("updated", &self.updated.get())?;
+        map.serialize_entry("failed", &self.failed.get())?;
+        map.end()
+    }
+}
+
+/// Runs a single update of a bulk update within `span` and logs its outcome
+async fn logged_update<Fut>(
+    span: Span,
+    subject: &str,
+    update: Fut,
+) -> Result<(), dco3::DracoonClientError>
+where
+    Fut: Future<Output = Result<(), dco3::DracoonClientError>>,
+{
+    async move {
+        let res = update.await;
+
+        match &res {
+            Ok(()) => info!(status = "success", "{} updated", subject),
+            Err(e) => error!(status = "failed", "{} update failed: {}", subject, e),
+        }
+
+        res
+    }
+    .instrument(span)
+    .await
+}
+
+/// Fetches all pages of a paginated list endpoint (`fetch` gets the offset of the page)
+async fn get_all<T, F, Fut>(fetch: F) -> Result<Vec<T>, AppError>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<RangedItems<T>, dco3::DracoonClientError>>,
+{
+    let mut items = vec![];
+    let mut offset = 0;
+
+    loop {
+        let page = fetch(offset).await?;
+        let total = page.range.total;
+        items.extend(page.items);
+
+        offset += LIST_PAGE_SIZE;
+        if offset >= total {
+            return Ok(items);
+        }
+    }
+}
+
+// API models for tests, built from their JSON representation
+#[cfg(test)]
+mod fixtures {
+    use console::Term;
+    use dco3::nodes::Node;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use tempfile::NamedTempFile;
+
+    pub fn from_json<T: DeserializeOwned>(json: Value) -> T {
+        serde_json::from_value(json).unwrap()
+    }
+
+    // room `room-{id}` below `/` - `fields` are added or replace the defaults
+    pub fn room(id: u64, fields: Value) -> Node {
+        let mut room = serde_json::json!({
+            "id": id, "type": "room", "name": format!("room-{id}"), "parentPath": "/"
+        });
+        if let (Some(room), Value::Object(fields)) = (room.as_object_mut(), fields) {
+            room.extend(fields);
+        }
+        from_json(room)
+    }
+
+    // terminal writing to a temp file (removed on drop) - never a TTY
+    pub struct FileTerm {
+        pub term: Term,
+        file: NamedTempFile,
+    }
+
+    impl FileTerm {
+        pub fn new() -> Self {
+            let file = NamedTempFile::new().unwrap();
+            let read = tempfile::tempfile().unwrap();
+            let write = file.as_file().try_clone().unwrap();
+
+            Self {
+                term: Term::read_write_pair(read, write),
+                file,
             }
         }
+
+        pub fn output(&self) -> String {
+            std::fs::read_to_string(self.file.path()).unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::FileTerm;
+
+    fn room(id: u64, parent_id: u64) -> Node {
+        fixtures::room(id, serde_json::json!({ "parentId": parent_id }))
+    }
+
+    #[test]
+    fn test_rebuild_sub_room_tree() {
+        // 10 is the data room, 20 an unrelated room
+        let rooms = vec![room(12, 11), room(11, 10), room(21, 20), room(13, 10)];
+
+        let ids = rebuild_sub_room_tree(10, rooms)
+            .into_iter()
+            .map(|room| room.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec![11, 13, 12]);
     }
 
-    Ok(())
+    #[test]
+    fn test_expiration_update_select() {
+        let date = |date: &str| date.parse::<DateTime<Utc>>().unwrap();
+        let update = ExpirationUpdate {
+            change: ExpirationChange::ExtendBy(chrono::TimeDelta::days(30)),
+            expires_before: Some(date("2024-06-01T00:00:00Z")),
+            now: date("2024-05-01T00:00:00Z"),
+        };
+
+        assert_eq!(update.select(None), None);
+        // the bound is exclusive
+        assert_eq!(update.select(Some(date("2024-06-01T00:00:00Z"))), None);
+        assert_eq!(
+            update.select(Some(date("2024-05-10T00:00:00Z"))),
+            Some((
+                date("2024-05-10T00:00:00Z"),
+                Some(date("2024-06-09T00:00:00Z"))
+            ))
+        );
+        // expired items are extended from now
+        assert_eq!(
+            update.select(Some(date("2024-04-10T00:00:00Z"))),
+            Some((
+                date("2024-04-10T00:00:00Z"),
+                Some(date("2024-05-31T00:00:00Z"))
+            ))
+        );
+
+        let update = ExpirationUpdate {
+            change: ExpirationChange::ExtendTo(date("2024-05-10T00:00:00Z")),
+            expires_before: None,
+            ..update
+        };
+        // unchanged
+        assert_eq!(update.select(Some(date("2024-05-10T00:00:00Z"))), None);
+        assert_eq!(
+            update.select(Some(date("2024-08-10T00:00:00Z"))),
+            Some((
+                date("2024-08-10T00:00:00Z"),
+                Some(date("2024-05-10T00:00:00Z"))
+            ))
+        );
+
+        let update = ExpirationUpdate {
+            change: ExpirationChange::Remove,
+            ..update
+        };
+        assert_eq!(
+            update.select(Some(date("2024-08-10T00:00:00Z"))),
+            Some((date("2024-08-10T00:00:00Z"), None))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_report_apply() {
+        let file_term = FileTerm::new();
+        let report = BulkReport::new("shares", false, vec![1, 2, 3]);
+
+        let res = report
+            .apply(&file_term.term, OutputFormat::Json, |id| async move {
+                match id {
+                    2 => Err(AppError::io_error("failed")),
+                    _ => Ok(()),
+                }
+            })
+            .await;
+
+        assert!(matches!(
+            res,
+            Err(AppError::PartialSuccess {
+                failed: 1,
+                total: 3
+            })
+        ));
+        let json: serde_json::Value = serde_json::from_str(&file_term.output()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"dry_run": false, "shares": [1, 2, 3], "updated": 2, "failed": 1})
+        );
+    }
 }
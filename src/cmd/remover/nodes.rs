@@ -1,70 +1,552 @@
-use std::sync::Arc;
+use std::{collections::HashSet, future::Future};
 
+use chrono::{DateTime, Utc};
 use dco3::{
     auth::Connected,
-    nodes::{Node, NodesFilter},
-    Dracoon, ListAllParams, Nodes, RangedItems,
+    models::FilterOperator,
+    nodes::{
+        Node, NodeType, NodesFilter, NodesSearchFilter, NodesSearchSortBy, NodesSortBy,
+        RoomPolicies,
+    },
+    Dracoon, DracoonClientError, ListAllParams, Nodes, RangedItems, Rooms, SortOrder, SortQuery,
 };
-use futures::StreamExt;
+use futures::{Stream, StreamExt, TryStreamExt};
 use futures_util::stream;
-use tokio::sync::Mutex;
-use tracing::{debug, error};
+use tracing::{debug, instrument, warn};
 
 use crate::cmd::errors::AppError;
 
+// search all levels below the parent
+pub const SEARCH_DEPTH_ALL: i8 = -1;
+
+const PAGE_SIZE: u64 = 500;
+// pages overlap by one node: a shift between two pages shows at the boundary
+const PAGE_STEP: u64 = PAGE_SIZE - 1;
+// pages fetched at the same time when all rooms are collected
+const CONCURRENT_PAGES: usize = 5;
+
+// sorted by creation date: new rooms are appended to the last page instead of shifting items
+fn room_params(offset: u64, sort: impl Into<Box<dyn SortQuery>>) -> ListAllParams {
+    ListAllParams::builder()
+        .with_offset(offset)
+        .with_limit(PAGE_SIZE)
+        .with_filter(NodesFilter::is_room())
+        .with_sort(sort)
+        .build()
+}
+
+// deduplicates the pages of a listing and detects rooms created or deleted meanwhile -
+// pages are passed in listing order
+#[derive(Default)]
+struct PageCheck {
+    seen: HashSet<u64>,
+    // total reported by the first page of the current pass
+    total: Option<u64>,
+    // last node of the previous page - also the first one of the next page
+    last_id: Option<u64>,
+    changed: bool,
+}
+
+impl PageCheck {
+    // returns the rooms not seen before
+    fn accept(&mut self, page: RangedItems<Node>) -> Vec<Node> {
+        let total = page.range.total;
+        if *self.total.get_or_insert(total) != total {
+            self.changed = true;
+        }
+
+        // the total may stay the same if rooms were created and deleted
+        let first_id = page.items.first().map(|room| room.id);
+        if self.last_id.is_some() && self.last_id != first_id {
+            self.changed = true;
+        }
+        self.last_id = page.items.last().map(|room| room.id);
+
+        page.items
+            .into_iter()
+            .filter(|room| self.seen.insert(room.id))
+            .collect()
+    }
+
+    fn seen(&self) -> u64 {
+        self.seen.len() as u64
+    }
+
+    // called after the last page of a pass
+    fn is_complete(&self) -> bool {
+        !self.changed && self.seen() >= self.total.unwrap_or(0)
+    }
+
+    // rooms already seen are kept
+    fn next_pass(&mut self) {
+        warn!(
+            "Nodes changed during listing ({} found, {} total) - reconciling",
+            self.seen(),
+            self.total.unwrap_or(0)
+        );
+        self.total = None;
+        self.last_id = None;
+        self.changed = false;
+    }
+
+    fn warn_incomplete(&self) {
+        if !self.is_complete() {
+            warn!(
+                "Nodes still changing after reconciliation ({} found, {} total)",
+                self.seen(),
+                self.total.unwrap_or(0)
+            );
+        }
+    }
+}
+
+struct PageState<F> {
+    fetch: F,
+    offset: Option<u64>,
+    check: PageCheck,
+    reconciled: bool,
+}
+
+// Pages are fetched one after another - only one page is held in memory at a time.
+// Rooms are deduplicated by id. If rooms were created or deleted during the listing,
+// items may have shifted between pages (a different total or a mismatch at the overlapping
+// page boundary): a second pass picks up the missed rooms.
+fn paged<F, Fut>(fetch: F) -> impl Stream<Item = Result<Vec<Node>, AppError>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<RangedItems<Node>, DracoonClientError>>,
+{
+    let state = PageState {
+        fetch,
+        offset: Some(0),
+        check: PageCheck::default(),
+        reconciled: false,
+    };
+
+    stream::try_unfold(state, |mut state| async move {
+        let Some(offset) = state.offset else {
+            return Ok(None);
+        };
+
+        let page = (state.fetch)(offset).await?;
+        let total = page.range.total;
+        let rooms = state.check.accept(page);
+
+        state.offset = if offset + PAGE_SIZE < total {
+            Some(offset + PAGE_STEP)
+        } else if !state.reconciled && !state.check.is_complete() {
+            state.check.next_pass();
+            state.reconciled = true;
+            Some(0)
+        } else {
+            state.check.warn_incomplete();
+            None
+        };
+
+        Ok(Some((rooms, state)))
+    })
+}
+
+// Same as `paged` for callers collecting all rooms anyway: the offsets are known from the
+// first page, the remaining pages are fetched concurrently (in order).
+async fn collect_paged<F, Fut>(fetch: F) -> Result<Vec<Node>, AppError>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<RangedItems<Node>, DracoonClientError>>,
+{
+    let mut check = PageCheck::default();
+    let mut rooms = vec![];
+
+    for pass in 0..2 {
+        if pass > 0 {
+            check.next_pass();
+        }
+
+        let first = fetch(0).await?;
+        let total = first.range.total;
+        rooms.extend(check.accept(first));
+
+        // a page is needed as long as nodes follow its first (overlapping) node
+        let offsets = (1..)
+            .map(|page| page * PAGE_STEP)
+            .take_while(|offset| offset + 1 < total);
+        let mut pages = stream::iter(offsets).map(&fetch).buffered(CONCURRENT_PAGES);
+
+        while let Some(page) = pages.try_next().await? {
+            rooms.extend(check.accept(page));
+        }
+
+        if check.is_complete() {
+            return Ok(rooms);
+        }
+    }
+
+    check.warn_incomplete();
+    Ok(rooms)
+}
+
+// the expiration filter is applied per day (inclusive) - callers check the exact window
+fn file_params(
+    offset: u64,
+    expire_from: DateTime<Utc>,
+    expire_until: DateTime<Utc>,
+) -> ListAllParams {
+    ListAllParams::builder()
+        .with_offset(offset)
+        .with_limit(PAGE_SIZE)
+        .with_filter(NodesSearchFilter::is_file())
+        .with_filter(NodesSearchFilter::expire_at_after(
+            expire_from.format("%Y-%m-%d").to_string(),
+        ))
+        .with_filter(NodesSearchFilter::expire_at_before(
+            expire_until.format("%Y-%m-%d").to_string(),
+        ))
+        .with_sort(NodesSearchSortBy::created_at(SortOrder::Asc))
+        .build()
+}
+
+// files and folders - rooms are listed separately
+fn content_params(offset: u64) -> ListAllParams {
+    ListAllParams::builder()
+        .with_offset(offset)
+        .with_limit(PAGE_SIZE)
+        .with_filter(NodesSearchFilter::Type(FilterOperator::Neq, NodeType::Room))
+        .with_sort(NodesSearchSortBy::created_at(SortOrder::Asc))
+        .build()
+}
+
+async fn fetch_child_rooms(
+    dracoon: &Dracoon<Connected>,
+    parent_id: Option<u64>,
+    offset: u64,
+) -> Result<RangedItems<Node>, DracoonClientError> {
+    dracoon
+        .nodes()
+        .get_nodes(
+            parent_id,
+            None,
+            Some(room_params(offset, NodesSortBy::created_at(SortOrder::Asc))),
+        )
+        .await
+}
+
+async fn fetch_search_rooms(
+    dracoon: &Dracoon<Connected>,
+    search: &str,
+    parent_id: Option<u64>,
+    depth_level: i8,
+    offset: u64,
+) -> Result<RangedItems<Node>, DracoonClientError> {
+    dracoon
+        .nodes()
+        .search_nodes(
+            search,
+            parent_id,
+            Some(depth_level),
+            Some(room_params(
+                offset,
+                NodesSearchSortBy::created_at(SortOrder::Asc),
+            )),
+        )
+        .await
+}
+
+/// Streams all rooms below the parent (root if `None`) down to `depth_level` via node search.
+/// Depth 0 returns the direct children, 1 children and grand children, -1 the whole subtree.
+pub fn search_room_pages(
+    dracoon: &Dracoon<Connected>,
+    parent_id: Option<u64>,
+    depth_level: i8,
+) -> impl Stream<Item = Result<Vec<Node>, AppError>> {
+    let dracoon = dracoon.clone();
+
+    paged(move |offset| {
+        let dracoon = dracoon.clone();
+        async move { fetch_search_rooms(&dracoon, "*", parent_id, depth_level, offset).await }
+    })
+}
+
+/// Streams all files below the parent on all levels (incl. sub rooms) via node search
+/// that expire between the days of `expire_from` and `expire_until`
+pub fn search_file_pages(
+    dracoon: &Dracoon<Connected>,
+    parent_id: u64,
+    expire_from: DateTime<Utc>,
+    expire_until: DateTime<Utc>,
+) -> impl Stream<Item = Result<Vec<Node>, AppError>> {
+    let dracoon = dracoon.clone();
+
+    paged(move |offset| {
+        let dracoon = dracoon.clone();
+        async move {
+            dracoon
+                .nodes()
+                .search_nodes(
+                    "*",
+                    Some(parent_id),
+                    Some(SEARCH_DEPTH_ALL),
+                    Some(file_params(offset, expire_from, expire_until)),
+                )
+                .await
+        }
+    })
+}
+
+/// Streams all files and folders below the parent on all levels (incl. sub rooms) via node search
+pub fn search_content_pages(
+    dracoon: &Dracoon<Connected>,
+    parent_id: u64,
+) -> impl Stream<Item = Result<Vec<Node>, AppError>> {
+    let dracoon = dracoon.clone();
+
+    paged(move |offset| {
+        let dracoon = dracoon.clone();
+        async move {
+            dracoon
+                .nodes()
+                .search_nodes(
+                    "*",
+                    Some(parent_id),
+                    Some(SEARCH_DEPTH_ALL),
+                    Some(content_params(offset)),
+                )
+                .await
+        }
+    })
+}
+
+/// Full path of the node (parent path and name)
+pub fn node_path(node: &Node) -> String {
+    join_path(node.parent_path.as_deref(), &node.name)
+}
+
+/// Full path from a parent path (`/` if missing) and a name
+pub fn join_path(parent_path: Option<&str>, name: &str) -> String {
+    format!("{}{}", parent_path.unwrap_or("/"), name)
+}
+
+#[instrument(skip(dracoon))]
 pub async fn get_all_nodes(
     dracoon: Dracoon<Connected>,
     parent_id: Option<u64>,
-) -> Result<RangedItems<Node>, AppError> {
-    // Initial request
-    let params = ListAllParams::builder()
-        .with_offset(0)
-        .with_limit(500)
-        .with_filter(NodesFilter::is_room())
-        .build();
-    let results = dracoon
-        .nodes()
-        .get_nodes(parent_id, None, Some(params))
-        .await?;
-    let total = results.range.total;
-    let shared_results = Arc::new(Mutex::new(results.clone()));
-
-    // Subsequent requests
-    let reqs = (500..=total)
-        .step_by(500)
-        .map(|offset| {
-            let params = ListAllParams::builder()
-                .with_offset(offset)
-                .with_limit(500)
-                .with_filter(NodesFilter::is_room())
-                .build();
-
-            dracoon.nodes().get_nodes(parent_id, None, Some(params))
-        })
-        .collect::<Vec<_>>();
-
-    stream::iter(reqs)
-        .for_each_concurrent(5, |f| {
-            let shared_results_clone = Arc::clone(&shared_results);
-            async move {
-                match f.await {
-                    Ok(mut nodes) => {
-                        let mut shared_results = shared_results_clone.lock().await;
-                        shared_results.items.append(&mut nodes.items);
-                    }
-                    Err(e) => {
-                        error!("Failed to fetch nodes: {}", e);
-                    }
-                }
-            }
-        })
-        .await;
+) -> Result<Vec<Node>, AppError> {
+    let rooms = collect_paged(|offset| fetch_child_rooms(&dracoon, parent_id, offset)).await?;
 
-    let results = shared_results.lock().await.clone();
     debug!(
         "Fetched {} child nodes from parent {}",
-        results.items.len(),
+        rooms.len(),
         parent_id.unwrap_or(0)
     );
-    Ok(results)
+    Ok(rooms)
+}
+
+/// Searches rooms by name (`*` as wildcard) on all levels, all result pages
+#[instrument(skip(dracoon))]
+pub async fn search_rooms_by_name(
+    dracoon: &Dracoon<Connected>,
+    name: &str,
+) -> Result<Vec<Node>, AppError> {
+    let rooms =
+        collect_paged(|offset| fetch_search_rooms(dracoon, name, None, SEARCH_DEPTH_ALL, offset))
+            .await?;
+
+    debug!("Found {} rooms named '{}'", rooms.len(), name);
+    Ok(rooms)
+}
+
+#[instrument(skip(dracoon))]
+pub async fn search_rooms(
+    dracoon: &Dracoon<Connected>,
+    parent_id: Option<u64>,
+    depth_level: i8,
+) -> Result<Vec<Node>, AppError> {
+    let rooms =
+        collect_paged(|offset| fetch_search_rooms(dracoon, "*", parent_id, depth_level, offset))
+            .await?;
+
+    debug!(
+        "Found {} rooms below parent {} (depth {})",
+        rooms.len(),
+        parent_id.unwrap_or(0),
+        depth_level
+    );
+    Ok(rooms)
+}
+
+/// Fetches the policies of all rooms (10 concurrent requests), failures are returned per room
+#[instrument(skip_all, fields(rooms = rooms.len()))]
+pub async fn get_room_policies(
+    dracoon: &Dracoon<Connected>,
+    rooms: Vec<Node>,
+) -> Vec<(Node, Result<RoomPolicies, DracoonClientError>)> {
+    let policy_futures = rooms.into_iter().map(|room| {
+        let dracoon = dracoon.clone();
+        async move {
+            let policies = dracoon.nodes().get_room_policies(room.id).await;
+            (room, policies)
+        }
+    });
+
+    stream::iter(policy_futures)
+        .buffer_unordered(10)
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::cmd::remover::fixtures::from_json;
+
+    fn page(ids: &[u64], offset: u64) -> RangedItems<Node> {
+        let items = ids
+            .iter()
+            .skip(offset as usize)
+            .take(PAGE_SIZE as usize)
+            .map(|id| serde_json::json!({ "id": id, "type": "room", "name": format!("room-{id}") }))
+            .collect::<Vec<_>>();
+
+        from_json(serde_json::json!({
+            "range": { "offset": offset, "limit": PAGE_SIZE, "total": ids.len() },
+            "items": items
+        }))
+    }
+
+    #[test]
+    fn test_file_params_filter_expiration_days() {
+        let from = "2024-05-01T12:00:00Z".parse().unwrap();
+        let until = "2024-05-15T08:30:00Z".parse().unwrap();
+
+        let params = file_params(0, from, until);
+
+        assert_eq!(
+            params.filter_to_string(),
+            "type:eq:file|expireAt:ge:2024-05-01|expireAt:le:2024-05-15"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paged_fetches_all_pages() {
+        let ids = (0..1001).collect::<Vec<_>>();
+        let offsets = Mutex::new(vec![]);
+
+        let rooms: Vec<Node> = paged(|offset| {
+            offsets.lock().unwrap().push(offset);
+            let page = page(&ids, offset);
+            async move { Ok(page) }
+        })
+        .try_concat()
+        .await
+        .unwrap();
+
+        assert_eq!(rooms.len(), 1001);
+        assert_eq!(*offsets.lock().unwrap(), vec![0, 499, 998]);
+    }
+
+    #[tokio::test]
+    async fn test_paged_reconciles_rooms_deleted_mid_listing() {
+        let ids = Mutex::new((0..1000).collect::<Vec<u64>>());
+
+        let rooms: Vec<Node> = paged(|offset| {
+            let mut ids = ids.lock().unwrap();
+            let page = page(&ids, offset);
+            // room 0 is deleted after the first page: room 500 shifts to the first page
+            if offset == 0 && ids[0] == 0 {
+                ids.remove(0);
+            }
+            async move { Ok(page) }
+        })
+        .try_concat()
+        .await
+        .unwrap();
+
+        let mut found = rooms.into_iter().map(|room| room.id).collect::<Vec<_>>();
+        found.sort_unstable();
+
+        assert_eq!(found, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_collect_paged_fetches_all_pages() {
+        let ids = (0..2001).collect::<Vec<_>>();
+        let offsets = Mutex::new(vec![]);
+
+        let rooms = collect_paged(|offset| {
+            offsets.lock().unwrap().push(offset);
+            let page = page(&ids, offset);
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+
+        // returned in listing order
+        let found = rooms.into_iter().map(|room| room.id).collect::<Vec<_>>();
+        assert_eq!(found, ids);
+        assert_eq!(*offsets.lock().unwrap(), vec![0, 499, 998, 1497, 1996]);
+    }
+
+    #[tokio::test]
+    async fn test_collect_paged_reconciles_rooms_deleted_mid_listing() {
+        let ids = Mutex::new((0..1000).collect::<Vec<u64>>());
+
+        let rooms = collect_paged(|offset| {
+            let mut ids = ids.lock().unwrap();
+            let page = page(&ids, offset);
+            if offset == 0 && ids[0] == 0 {
+                ids.remove(0);
+            }
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+
+        let mut found = rooms.into_iter().map(|room| room.id).collect::<Vec<_>>();
+        found.sort_unstable();
+
+        assert_eq!(found, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_paged_reconciles_shift_with_same_total() {
+        let ids = Mutex::new((0..1000).collect::<Vec<u64>>());
+
+        let rooms: Vec<Node> = paged(|offset| {
+            let mut ids = ids.lock().unwrap();
+            let page = page(&ids, offset);
+            // room 0 is deleted and room 1000 created after the first page: same total
+            if offset == 0 && ids[0] == 0 {
+                ids.remove(0);
+                ids.push(1000);
+            }
+            async move { Ok(page) }
+        })
+        .try_concat()
+        .await
+        .unwrap();
+
+        let mut found = rooms.into_iter().map(|room| room.id).collect::<Vec<_>>();
+        found.sort_unstable();
+
+        assert_eq!(found, (0..=1000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_paged_single_pass_without_changes() {
+        let ids = (0..1000).collect::<Vec<_>>();
+        let fetches = Mutex::new(0);
+
+        let rooms: Vec<Node> = paged(|offset| {
+            *fetches.lock().unwrap() += 1;
+            let page = page(&ids, offset);
+            async move { Ok(page) }
+        })
+        .try_concat()
+        .await
+        .unwrap();
+
+        assert_eq!(rooms.len(), 1000);
+        assert_eq!(*fetches.lock().unwrap(), 3);
+    }
 }
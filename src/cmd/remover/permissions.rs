@@ -0,0 +1,102 @@
+use console::Term;
+use dco3::nodes::Node;
+use dialoguer::Select;
+use tracing::{info, warn};
+
+use crate::cmd::{errors::AppError, utils::strings::format_error_message};
+
+use super::nodes::node_path;
+
+pub(super) fn has_manage_permission(room: &Node) -> bool {
+    room.permissions.as_ref().is_some_and(|p| p.manage)
+}
+
+/// Checks the current user's permissions on all rooms before any room is modified.
+/// Rooms without `manage` permission are either skipped or the run is aborted.
+pub fn check_manage_permissions(
+    term: &Term,
+    rooms: Vec<Node>,
+    skip_unmanaged: bool,
+) -> Result<Vec<Node>, AppError> {
+    let (managed, unmanaged): (Vec<_>, Vec<_>) = rooms.into_iter().partition(has_manage_permission);
+
+    if unmanaged.is_empty() {
+        return Ok(managed);
+    }
+
+    print_unmanaged(term, &unmanaged, unmanaged.len() + managed.len())?;
+
+    let skip = skip_unmanaged
+        || Select::new()
+            .with_prompt("How do you want to proceed?")
+            .items(&["Skip these rooms", "Abort"])
+            .default(1)
+            .interact_on_opt(term)
+            .ok()
+            .flatten()
+            == Some(0);
+
+    if !skip {
+        return Err(AppError::MissingManagePermission(unmanaged.len()));
+    }
+
+    info!(
+        "Skipping {} rooms without manage permission",
+        unmanaged.len()
+    );
+
+    Ok(managed)
+}
+
+/// Lists the rooms without manage permission (of `total` rooms)
+pub(super) fn print_unmanaged(
+    term: &Term,
+    unmanaged: &[Node],
+    total: usize,
+) -> Result<(), AppError> {
+    let msg = format_error_message(&format!(
+        "Missing room admin rights (manage permission) on {} of {} rooms:",
+        unmanaged.len(),
+        total
+    ));
+    term.write_line(&msg)?;
+
+    for room in unmanaged {
+        warn!(
+            "Missing manage permission on room {} ({})",
+            room.id, room.name
+        );
+        term.write_line(&format!("  ► {} ({})", room.id, node_path(room)))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::{room as room_fixture, FileTerm};
+
+    fn room(id: u64, manage: Option<bool>) -> Node {
+        let permissions = manage.map(|manage| {
+            serde_json::json!({
+                "manage": manage, "read": true, "create": true, "change": true, "delete": true,
+                "manageDownloadShare": true, "manageUploadShare": true, "readRecycleBin": true,
+                "restoreRecycleBin": true, "deleteRecycleBin": true
+            })
+        });
+        room_fixture(id, serde_json::json!({ "permissions": permissions }))
+    }
+
+    #[test]
+    fn test_skip_unmanaged_rooms() {
+        let rooms = vec![room(1, Some(true)), room(2, Some(false)), room(3, None)];
+
+        let file_term = FileTerm::new();
+
+        let managed = check_manage_permissions(&file_term.term, rooms, true).unwrap();
+
+        assert_eq!(managed.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1]);
+        assert!(file_term.output().contains("on 2 of 3 rooms"));
+    }
+}
@@ -0,0 +1,288 @@
+use console::Term;
+use dco3::{auth::Connected, nodes::Node, Dracoon};
+use dialoguer::{Confirm, FuzzySelect, Input};
+use tracing::debug;
+
+use crate::cmd::errors::AppError;
+
+use super::nodes::{get_all_nodes, node_path, search_rooms_by_name};
+
+enum PickerAction {
+    SelectCurrent,
+    Back,
+    Search,
+    Done,
+    Open(Box<Node>),
+}
+
+impl PickerAction {
+    fn label(&self) -> String {
+        match self {
+            PickerAction::SelectCurrent => "✔ Select this room".to_string(),
+            PickerAction::Back => "⬆ Back".to_string(),
+            PickerAction::Search => "🔍 Search all rooms by name".to_string(),
+            PickerAction::Done => "▶ Done".to_string(),
+            PickerAction::Open(room) => match room.cnt_rooms {
+                Some(cnt) if cnt > 0 => format!("{} ({} rooms) ▸", room.name, cnt),
+                _ => room.name.clone(),
+            },
+        }
+    }
+}
+
+// picker state, separate from the prompts
+#[derive(Default)]
+struct Picker {
+    selected: Vec<Node>,
+    path: Vec<Node>,
+}
+
+enum PickerStep {
+    Continue,
+    Search,
+    Select(Box<Node>),
+    Done,
+}
+
+impl Picker {
+    fn actions(&self, rooms: Vec<Node>) -> Vec<PickerAction> {
+        let mut actions = vec![];
+        if !self.path.is_empty() {
+            actions.push(PickerAction::SelectCurrent);
+            actions.push(PickerAction::Back);
+        }
+        actions.push(PickerAction::Search);
+        if !self.selected.is_empty() {
+            actions.push(PickerAction::Done);
+        }
+        actions.extend(
+            rooms
+                .into_iter()
+                .map(|room| PickerAction::Open(Box::new(room))),
+        );
+
+        actions
+    }
+
+    fn apply(&mut self, action: PickerAction) -> PickerStep {
+        match action {
+            PickerAction::SelectCurrent => self
+                .path
+                .last()
+                .cloned()
+                .map_or(PickerStep::Continue, |room| {
+                    PickerStep::Select(Box::new(room))
+                }),
+            PickerAction::Back => {
+                self.path.pop();
+                PickerStep::Continue
+            }
+            PickerAction::Search => PickerStep::Search,
+            PickerAction::Done => PickerStep::Done,
+            PickerAction::Open(room) => {
+                self.path.push(*room);
+                PickerStep::Continue
+            }
+        }
+    }
+
+    // returns `false` if the room was already selected
+    fn select(&mut self, room: Node) -> bool {
+        if self.selected.iter().any(|selected| selected.id == room.id) {
+            return false;
+        }
+
+        self.selected.push(room);
+        true
+    }
+
+    fn breadcrumb(&self) -> String {
+        self.path
+            .iter()
+            .map(|room| room.name.as_str())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Lets the user browse the room hierarchy (starting at the root) and pick one or more parent rooms
+pub async fn pick_rooms(dracoon: &Dracoon<Connected>, term: &Term) -> Result<Vec<u64>, AppError> {
+    if !term.is_term() {
+        return Err(AppError::InvalidArgument(
+            "No room id given - the interactive room picker requires a TTY.".to_string(),
+        ));
+    }
+
+    let mut picker = Picker::default();
+
+    loop {
+        let current = picker.path.last().map(|room| room.id);
+        let rooms = get_all_nodes(dracoon.clone(), current).await?;
+
+        let mut actions = picker.actions(rooms);
+        let labels = actions.iter().map(PickerAction::label).collect::<Vec<_>>();
+
+        let choice = FuzzySelect::new()
+            .with_prompt(format!(
+                "/{} (type to filter, esc to finish)",
+                picker.breadcrumb()
+            ))
+            .items(&labels)
+            .default(0)
+            .interact_on_opt(term)?;
+
+        let Some(choice) = choice else {
+            break;
+        };
+
+        let room = match picker.apply(actions.swap_remove(choice)) {
+            PickerStep::Continue => None,
+            PickerStep::Search => search_room(dracoon, term).await?,
+            PickerStep::Select(room) => Some(*room),
+            PickerStep::Done => break,
+        };
+
+        if let Some(room) = room {
+            let (id, name) = (room.id, room.name.clone());
+            if !picker.select(room) {
+                term.write_line(&format!("► Already selected: {name} ({id})"))?;
+                continue;
+            }
+
+            debug!("Selected room {} ({})", id, name);
+            term.write_line(&format!("► Selected: {name} ({id})"))?;
+
+            let another = Confirm::new()
+                .with_prompt("Select another room?")
+                .default(false)
+                .interact_on(term)?;
+
+            if !another {
+                break;
+            }
+        }
+    }
+
+    if picker.selected.is_empty() {
+        return Err(AppError::InvalidArgument("No room selected.".to_string()));
+    }
+
+    Ok(picker.selected.into_iter().map(|room| room.id).collect())
+}
+
+async fn search_room(dracoon: &Dracoon<Connected>, term: &Term) -> Result<Option<Node>, AppError> {
+    let search: String = Input::new()
+        .with_prompt("Room name (use * as wildcard)")
+        .interact_text_on(term)?;
+
+    let results = search_rooms_by_name(dracoon, &search).await?;
+
+    if results.is_empty() {
+        term.write_line(&format!("► No rooms found for '{search}'."))?;
+        return Ok(None);
+    }
+
+    let labels = results
+        .iter()
+        .map(|room| format!("{} ({})", node_path(room), room.id))
+        .collect::<Vec<_>>();
+
+    let choice = FuzzySelect::new()
+        .with_prompt("Select room (esc to go back)")
+        .items(&labels)
+        .default(0)
+        .interact_on_opt(term)?;
+
+    Ok(choice.map(|idx| results[idx].clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::room as room_fixture;
+
+    fn room(id: u64, name: &str) -> Node {
+        room_fixture(id, serde_json::json!({ "name": name }))
+    }
+
+    fn labels(actions: &[PickerAction]) -> Vec<String> {
+        actions.iter().map(PickerAction::label).collect()
+    }
+
+    #[test]
+    fn test_picker_actions() {
+        let mut picker = Picker::default();
+
+        // root: nothing to select or go back to
+        let actions = picker.actions(vec![room(1, "Clients")]);
+        assert_eq!(
+            labels(&actions),
+            vec!["🔍 Search all rooms by name", "Clients"]
+        );
+
+        picker.path.push(room(1, "Clients"));
+        picker.selected.push(room(2, "ACME"));
+        let actions = picker.actions(vec![]);
+        assert_eq!(
+            labels(&actions),
+            vec![
+                "✔ Select this room",
+                "⬆ Back",
+                "🔍 Search all rooms by name",
+                "▶ Done"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_picker_apply() {
+        let mut picker = Picker::default();
+
+        assert!(matches!(
+            picker.apply(PickerAction::SelectCurrent),
+            PickerStep::Continue
+        ));
+        assert!(matches!(
+            picker.apply(PickerAction::Open(Box::new(room(1, "Clients")))),
+            PickerStep::Continue
+        ));
+        assert!(matches!(
+            picker.apply(PickerAction::Open(Box::new(room(2, "ACME")))),
+            PickerStep::Continue
+        ));
+        assert_eq!(picker.breadcrumb(), "Clients/ACME");
+
+        assert!(matches!(
+            picker.apply(PickerAction::SelectCurrent),
+            PickerStep::Select(room) if room.id == 2
+        ));
+        assert!(matches!(
+            picker.apply(PickerAction::Back),
+            PickerStep::Continue
+        ));
+        assert_eq!(picker.breadcrumb(), "Clients");
+
+        assert!(matches!(
+            picker.apply(PickerAction::Search),
+            PickerStep::Search
+        ));
+        assert!(matches!(picker.apply(PickerAction::Done), PickerStep::Done));
+    }
+
+    #[test]
+    fn test_picker_select_dedupes() {
+        let mut picker = Picker::default();
+
+        assert!(picker.select(room(2, "ACME")));
+        assert!(picker.select(room(3, "Globex")));
+        // e.g. picked again via search
+        assert!(!picker.select(room(2, "ACME")));
+
+        let ids = picker
+            .selected
+            .iter()
+            .map(|room| room.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 3]);
+    }
+}
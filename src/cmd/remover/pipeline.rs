@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+
+use console::Term;
+use dco3::{auth::Connected, nodes::Node, Dracoon};
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::mpsc::{self, Sender};
+use tracing::{debug, error, info, instrument, warn, Instrument};
+
+use crate::cmd::{errors::AppError, models::UpdateCount};
+
+use super::{
+    list_home_room_and_sub_room_ids,
+    managed::traverse_managed_rooms,
+    nodes::{search_room_pages, SEARCH_DEPTH_ALL},
+    permissions::{has_manage_permission, print_unmanaged},
+    revert_expiration_date_stream, SEARCH_DEPTH_SUB_ROOMS,
+};
+
+// room ids buffered between discovery and updates (one search page) -
+// discovery waits while the buffer is full
+const CHANNEL_CAPACITY: usize = 500;
+
+#[derive(Debug)]
+pub enum RoomSource {
+    Parents(Vec<u64>),
+    AllManaged,
+}
+
+/// Discovers rooms and updates them at the same time: discovery sends room ids through a
+/// bounded channel and the updater starts with the first room found.
+/// Rooms cannot be checked before the first update: rooms without manage permission are skipped
+/// and listed at the end (`--stream` requires `--skip-unmanaged`).
+/// Returns the number of updated and failed rooms - also if discovery failed.
+#[instrument(skip(term, dracoon))]
+pub async fn stream_remove_expiration(
+    term: &Term,
+    dracoon: Dracoon<Connected>,
+    source: RoomSource,
+) -> (UpdateCount, Result<(), AppError>) {
+    let (tx, mut rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+    let discovery = tokio::spawn(discover_rooms(dracoon.clone(), source, tx).in_current_span());
+
+    let room_ids = stream::poll_fn(move |cx| rx.poll_recv(cx));
+    let (count, res) = revert_expiration_date_stream(dracoon, room_ids).await;
+
+    let discovered = match discovery.await {
+        Ok(discovered) => discovered,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => {
+            error!("Room discovery cancelled: {}", e);
+            Err(AppError::Aborted("room discovery cancelled".to_string()))
+        }
+    };
+
+    info!("Updated {} rooms", count.updated);
+    let res = print_outcome(term, count, res, discovered);
+
+    (count, res)
+}
+
+// rooms updated before a failure are reported as well - a failed discovery takes precedence
+fn print_outcome(
+    term: &Term,
+    count: UpdateCount,
+    res: Result<(), AppError>,
+    discovered: Result<(usize, Vec<Node>), AppError>,
+) -> Result<(), AppError> {
+    term.write_line(&format!("► Updated {} rooms.", count.updated))?;
+
+    let (found, unmanaged) = discovered.inspect_err(|e| {
+        error!(
+            "Room discovery failed after {} updated rooms: {}",
+            count.updated, e
+        );
+    })?;
+
+    if !unmanaged.is_empty() {
+        print_unmanaged(term, &unmanaged, found)?;
+        info!(
+            "Skipped {} rooms without manage permission",
+            unmanaged.len()
+        );
+    }
+
+    res
+}
+
+struct Discovery {
+    tx: Sender<u64>,
+    seen: HashSet<u64>,
+    // all managed rooms: other rooms are out of scope, not skipped
+    managed_only: bool,
+    unmanaged: Vec<Node>,
+}
+
+impl Discovery {
+    // returns `false` once the updater stopped receiving
+    async fn send(&mut self, rooms: Vec<Node>) -> bool {
+        for room in rooms {
+            // fallback listings may repeat rooms already found via search
+            if !self.seen.insert(room.id) {
+                continue;
+            }
+
+            if !has_manage_permission(&room) {
+                if !self.managed_only {
+                    debug!("Skipping room {} without manage permission", room.id);
+                    self.unmanaged.push(room);
+                }
+                continue;
+            }
+
+            if self.tx.send(room.id).await.is_err() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // returns `false` if search failed and a fallback is needed
+    async fn send_search_pages(
+        &mut self,
+        pages: impl Stream<Item = Result<Vec<Node>, AppError>>,
+    ) -> bool {
+        let mut pages = std::pin::pin!(pages);
+
+        while let Some(page) = pages.next().await {
+            match page {
+                // permissions are required to skip unmanaged rooms
+                Ok(rooms) if rooms.iter().all(|room| room.permissions.is_some()) => {
+                    if !self.send(rooms).await {
+                        return true;
+                    }
+                }
+                Ok(_) => {
+                    warn!("Room search returned no permissions - falling back to listing");
+                    return false;
+                }
+                Err(e) => {
+                    warn!("Room search failed - falling back to listing: {}", e);
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+// returns the number of rooms found and the rooms without manage permission
+async fn discover_rooms(
+    dracoon: Dracoon<Connected>,
+    source: RoomSource,
+    tx: Sender<u64>,
+) -> Result<(usize, Vec<Node>), AppError> {
+    let mut discovery = Discovery {
+        tx,
+        seen: HashSet::new(),
+        managed_only: matches!(source, RoomSource::AllManaged),
+        unmanaged: vec![],
+    };
+
+    match source {
+        RoomSource::Parents(parent_ids) => {
+            for parent_id in parent_ids {
+                let pages = search_room_pages(&dracoon, Some(parent_id), SEARCH_DEPTH_SUB_ROOMS);
+
+                if !discovery.send_search_pages(pages).await {
+                    let rooms = list_home_room_and_sub_room_ids(dracoon.clone(), parent_id).await?;
+                    discovery.send(rooms).await;
+                }
+            }
+        }
+        RoomSource::AllManaged => {
+            let pages = search_room_pages(&dracoon, None, SEARCH_DEPTH_ALL);
+
+            if !discovery.send_search_pages(pages).await {
+                let rooms = traverse_managed_rooms(dracoon.clone()).await?;
+                discovery.send(rooms).await;
+            }
+        }
+    }
+
+    debug!("Discovered {} rooms", discovery.seen.len());
+
+    Ok((discovery.seen.len(), discovery.unmanaged))
+}
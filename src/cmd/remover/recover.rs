@@ -0,0 +1,401 @@
+use chrono::{DateTime, Utc};
+use console::Term;
+use dco3::{
+    auth::Connected,
+    nodes::{Node, UserType},
+    Dracoon,
+};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use tracing::{error, info, instrument, warn};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{CredentialOptions, DeletedBy, OutputFormat, PasswordAuth, RecoverOptions},
+};
+
+use super::{
+    collect_rooms,
+    confirm::confirm_bulk,
+    get_parent_ids,
+    managed::collect_managed_rooms,
+    nodes::join_path,
+    recycle_bin::{DeletedNode, DeletedNodeSummary, RecycleBin},
+};
+
+// deleted node ids per restore request
+const RESTORE_BATCH_SIZE: usize = 100;
+
+// deletion date range and deleting user
+#[derive(Debug, Default)]
+struct RecoverFilter {
+    after: Option<DateTime<Utc>>,
+    before: Option<DateTime<Utc>>,
+    deleted_by: Option<DeletedBy>,
+}
+
+impl RecoverFilter {
+    fn in_range(&self, date: DateTime<Utc>) -> bool {
+        self.after.is_none_or(|after| date >= after)
+            && self.before.is_none_or(|before| date < before)
+    }
+
+    // skips listing the versions if none can match
+    fn may_match(&self, summary: &DeletedNodeSummary) -> bool {
+        self.after
+            .is_none_or(|after| summary.last_deleted_at >= after)
+            && self
+                .before
+                .is_none_or(|before| summary.first_deleted_at < before)
+    }
+
+    fn matches(&self, node: &DeletedNode) -> bool {
+        let Some(deleted_at) = node.deleted_at else {
+            return false;
+        };
+
+        if !self.in_range(deleted_at) {
+            return false;
+        }
+
+        match (&self.deleted_by, &node.deleted_by) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(DeletedBy::System), Some(user)) => user.user_type == UserType::System,
+            (Some(DeletedBy::UserId(id)), Some(user)) => user.id == *id,
+            (Some(DeletedBy::UserName(name)), Some(user)) => user
+                .user_name
+                .as_deref()
+                .is_some_and(|user_name| user_name.eq_ignore_ascii_case(name)),
+        }
+    }
+}
+
+// matching versions of a node - only the most recently deleted one unless all are requested
+fn select_versions(
+    versions: Vec<DeletedNode>,
+    filter: &RecoverFilter,
+    all_versions: bool,
+) -> Vec<DeletedNode> {
+    let matching = versions.into_iter().filter(|node| filter.matches(node));
+
+    if all_versions {
+        return matching.collect();
+    }
+
+    matching
+        .max_by_key(|node| node.deleted_at)
+        .into_iter()
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct RecoverCandidate {
+    id: u64,
+    room_id: u64,
+    path: String,
+    size: Option<u64>,
+    deleted_at: Option<DateTime<Utc>>,
+    deleted_by: Option<String>,
+}
+
+impl RecoverCandidate {
+    fn new(room_id: u64, node: DeletedNode) -> Self {
+        let deleted_by = node.deleted_by.map(|user| match user.user_type {
+            UserType::System => "system".to_string(),
+            _ => user.user_name.unwrap_or_else(|| user.id.to_string()),
+        });
+
+        Self {
+            id: node.id,
+            room_id,
+            path: join_path(node.parent_path.as_deref(), &node.name),
+            size: node.size,
+            deleted_at: node.deleted_at,
+            deleted_by,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct RecoverReport {
+    rooms: usize,
+    dry_run: bool,
+    candidates: Vec<RecoverCandidate>,
+    restored: usize,
+    failed: usize,
+}
+
+impl RecoverReport {
+    fn print(&self, term: &Term, format: OutputFormat) -> Result<(), AppError> {
+        match format {
+            OutputFormat::Text if self.dry_run => {
+                term.write_line(&format!(
+                    "► Dry run - {} versions from {} rooms not restored.",
+                    self.candidates.len(),
+                    self.rooms
+                ))?;
+            }
+            OutputFormat::Text => {
+                term.write_line(&format!(
+                    "► Restored {} of {} versions from {} rooms ({} failed).",
+                    self.restored,
+                    self.candidates.len(),
+                    self.rooms,
+                    self.failed
+                ))?;
+            }
+            OutputFormat::Json => {
+                let json = serde_json::to_string_pretty(self)
+                    .map_err(|e| AppError::io_error(format!("Failed to serialize report: {e}")))?;
+                term.write_line(&json)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_candidates(term: &Term, candidates: &[RecoverCandidate]) -> Result<(), AppError> {
+    term.write_line(&format!("► Deleted versions found: {}", candidates.len()))?;
+
+    for candidate in candidates {
+        term.write_line(&format!(
+            "  ▶︎ {} ({}) - deleted {} by {}",
+            candidate.path,
+            candidate.id,
+            candidate
+                .deleted_at
+                .map_or_else(|| "unknown".to_string(), |date| date.to_rfc3339()),
+            candidate.deleted_by.as_deref().unwrap_or("unknown")
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Restores deleted versions from the recycle bins of the collected rooms
+#[instrument(name = "recover", skip(term, auth, credentials))]
+pub async fn handle_recover(
+    term: Term,
+    base_url: String,
+    opts: RecoverOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = recover(&term, &dracoon, opts).await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+async fn recover(
+    term: &Term,
+    dracoon: &Dracoon<Connected>,
+    opts: RecoverOptions,
+) -> Result<(), AppError> {
+    let rooms = if opts.all_managed {
+        collect_managed_rooms(dracoon.clone()).await?
+    } else {
+        let parent_ids = get_parent_ids(dracoon, term, &opts.rooms).await?;
+        collect_rooms(dracoon.clone(), &parent_ids).await?
+    };
+
+    let (rooms, skipped): (Vec<_>, Vec<_>) = rooms.into_iter().partition(can_restore);
+    if !skipped.is_empty() {
+        warn!(
+            "Skipping {} rooms without recycle bin permission",
+            skipped.len()
+        );
+        term.write_line(&format!(
+            "► Skipping {} rooms without recycle bin permission.",
+            skipped.len()
+        ))?;
+    }
+
+    let filter = RecoverFilter {
+        after: opts.deleted_after,
+        before: opts.deleted_before,
+        deleted_by: opts.deleted_by.clone(),
+    };
+
+    let recycle_bin = RecycleBin::new(dracoon)?;
+
+    let mut report = RecoverReport {
+        rooms: rooms.len(),
+        dry_run: opts.dry_run,
+        candidates: find_candidates(&recycle_bin, &rooms, &filter, opts.all_versions).await?,
+        ..Default::default()
+    };
+
+    if report.candidates.is_empty() {
+        return match opts.format {
+            OutputFormat::Text => Ok(term.write_line("► No deleted versions match.")?),
+            OutputFormat::Json => report.print(term, opts.format),
+        };
+    }
+
+    if opts.format == OutputFormat::Text {
+        print_candidates(term, &report.candidates)?;
+    }
+
+    if opts.dry_run {
+        info!(
+            "Dry run - {} versions not restored",
+            report.candidates.len()
+        );
+        return report.print(term, opts.format);
+    }
+
+    let prompt = format!("Restore {} deleted versions?", report.candidates.len());
+    if !confirm_bulk(term, prompt, opts.yes)? {
+        return Err(AppError::Aborted("nothing restored".to_string()));
+    }
+
+    let ids = report
+        .candidates
+        .iter()
+        .map(|candidate| candidate.id)
+        .collect::<Vec<_>>();
+
+    let mut results = stream::iter(ids.chunks(RESTORE_BATCH_SIZE))
+        .map(|batch| async {
+            let res = recycle_bin
+                .restore_deleted_nodes(batch, opts.on_conflict)
+                .await;
+            (batch.len(), res)
+        })
+        .buffer_unordered(10);
+
+    let mut first_error = None;
+    while let Some((count, res)) = results.next().await {
+        match res {
+            Ok(()) => report.restored += count,
+            Err(e) => {
+                error!("Restoring {} versions failed: {}", count, e);
+                report.failed += count;
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    info!(
+        restored = report.restored,
+        failed = report.failed,
+        "Recovery finished"
+    );
+    report.print(term, opts.format)?;
+
+    match first_error {
+        None => Ok(()),
+        Some(e) if report.restored == 0 => Err(e),
+        Some(_) => Err(AppError::PartialSuccess {
+            failed: report.failed,
+            total: report.candidates.len(),
+        }),
+    }
+}
+
+fn can_restore(room: &Node) -> bool {
+    room.permissions
+        .as_ref()
+        .is_some_and(|p| p.read_recycle_bin && p.restore_recycle_bin)
+}
+
+// lists the recycle bins of the rooms (10 concurrent rooms)
+async fn find_candidates(
+    recycle_bin: &RecycleBin<'_>,
+    rooms: &[Node],
+    filter: &RecoverFilter,
+    all_versions: bool,
+) -> Result<Vec<RecoverCandidate>, AppError> {
+    let mut room_stream = stream::iter(rooms)
+        .map(|room| async move {
+            let mut candidates = vec![];
+
+            for summary in recycle_bin.get_deleted_nodes(room.id).await? {
+                if !filter.may_match(&summary) {
+                    continue;
+                }
+
+                let versions = recycle_bin.get_deleted_versions(&summary).await?;
+                candidates.extend(
+                    select_versions(versions, filter, all_versions)
+                        .into_iter()
+                        .map(|node| RecoverCandidate::new(room.id, node)),
+                );
+            }
+
+            Ok::<_, AppError>(candidates)
+        })
+        .buffer_unordered(10);
+
+    let mut candidates = vec![];
+    while let Some(result) = room_stream.next().await {
+        candidates.extend(result?);
+    }
+
+    candidates.sort_by(|a, b| a.path.cmp(&b.path).then(a.deleted_at.cmp(&b.deleted_at)));
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::from_json;
+
+    fn deleted(id: u64, deleted_at: &str, user_type: &str, user_name: &str) -> DeletedNode {
+        from_json(serde_json::json!({
+            "id": id, "name": "report.pdf", "parentPath": "/Clients/ACME/",
+            "deletedAt": deleted_at,
+            "deletedBy": {
+                "id": id, "userType": user_type, "userName": user_name, "avatarUuid": "x"
+            }
+        }))
+    }
+
+    fn ids(nodes: Vec<DeletedNode>) -> Vec<u64> {
+        nodes.into_iter().map(|node| node.id).collect()
+    }
+
+    #[test]
+    fn test_select_versions() {
+        let versions = || {
+            vec![
+                deleted(1, "2024-05-01T10:00:00Z", "system", "system"),
+                deleted(2, "2024-05-20T10:00:00Z", "system", "system"),
+                deleted(3, "2024-05-25T10:00:00Z", "internal", "jdoe"),
+                deleted(4, "2024-06-02T10:00:00Z", "system", "system"),
+            ]
+        };
+        let filter = RecoverFilter {
+            after: Some("2024-05-01T00:00:00Z".parse().unwrap()),
+            before: Some("2024-06-01T00:00:00Z".parse().unwrap()),
+            deleted_by: None,
+        };
+
+        assert_eq!(
+            ids(select_versions(versions(), &filter, true)),
+            vec![1, 2, 3]
+        );
+        assert_eq!(ids(select_versions(versions(), &filter, false)), vec![3]);
+
+        let filter = RecoverFilter {
+            deleted_by: Some(DeletedBy::System),
+            ..filter
+        };
+        assert_eq!(ids(select_versions(versions(), &filter, false)), vec![2]);
+
+        let filter = RecoverFilter {
+            deleted_by: Some(DeletedBy::UserName("JDOE".to_string())),
+            ..Default::default()
+        };
+        assert_eq!(ids(select_versions(versions(), &filter, true)), vec![3]);
+    }
+}
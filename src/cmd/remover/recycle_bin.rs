@@ -0,0 +1,209 @@
+//! Recycle bin endpoints (`/nodes/{id}/deleted_nodes`) - not covered by dco3 yet, the requests
+//! reuse the authentication and base url of the connected client.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dco3::{
+    auth::{models::DracoonErrorResponse, Connected},
+    nodes::UserInfo,
+    Dracoon, RangedItems,
+};
+use reqwest::{header, Client, Method, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::cmd::{errors::AppError, models::ConflictStrategy};
+
+const API_PREFIX: &str = "/api/v4/nodes";
+const PAGE_SIZE: u64 = 500;
+// per request, incl. reading the response
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Deleted node (all versions with the same name and parent) in a recycle bin
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedNodeSummary {
+    pub parent_id: u64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub first_deleted_at: DateTime<Utc>,
+    pub last_deleted_at: DateTime<Utc>,
+}
+
+/// Single deleted version of a node
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedNode {
+    pub id: u64,
+    pub parent_path: Option<String>,
+    pub name: String,
+    pub size: Option<u64>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<UserInfo>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreDeletedNodesRequest<'a> {
+    deleted_node_ids: &'a [u64],
+    resolution_strategy: &'static str,
+    keep_share_links: bool,
+}
+
+/// Recycle bin client - one per run, the HTTP client (connection pool, timeout) is reused
+/// for all requests
+pub struct RecycleBin<'a> {
+    dracoon: &'a Dracoon<Connected>,
+    client: Client,
+}
+
+impl<'a> RecycleBin<'a> {
+    pub fn new(dracoon: &'a Dracoon<Connected>) -> Result<Self, AppError> {
+        Ok(Self {
+            dracoon,
+            client: http_client(TIMEOUT)?,
+        })
+    }
+
+    /// Lists all deleted nodes in the recycle bin of the room
+    #[instrument(skip(self))]
+    pub async fn get_deleted_nodes(
+        &self,
+        room_id: u64,
+    ) -> Result<Vec<DeletedNodeSummary>, AppError> {
+        self.get_all_pages(&format!("{API_PREFIX}/{room_id}/deleted_nodes"), &[])
+            .await
+    }
+
+    /// Lists all deleted versions of the node in the given parent
+    #[instrument(skip(self, summary), fields(parent_id = summary.parent_id, name = summary.name))]
+    pub async fn get_deleted_versions(
+        &self,
+        summary: &DeletedNodeSummary,
+    ) -> Result<Vec<DeletedNode>, AppError> {
+        self.get_all_pages(
+            &format!("{API_PREFIX}/{}/deleted_nodes/versions", summary.parent_id),
+            &[("type", &summary.node_type), ("name", &summary.name)],
+        )
+        .await
+    }
+
+    /// Restores the deleted versions to their original parent
+    #[instrument(skip(self, deleted_node_ids), fields(count = deleted_node_ids.len()))]
+    pub async fn restore_deleted_nodes(
+        &self,
+        deleted_node_ids: &[u64],
+        on_conflict: ConflictStrategy,
+    ) -> Result<(), AppError> {
+        let body = RestoreDeletedNodesRequest {
+            deleted_node_ids,
+            resolution_strategy: match on_conflict {
+                ConflictStrategy::Autorename => "autorename",
+                ConflictStrategy::Overwrite => "overwrite",
+                ConflictStrategy::Fail => "fail",
+            },
+            keep_share_links: false,
+        };
+
+        let request = self
+            .request(
+                Method::POST,
+                &format!("{API_PREFIX}/deleted_nodes/actions/restore"),
+            )
+            .await?
+            .json(&body);
+
+        send(request).await.map(|_| ())
+    }
+
+    async fn get_all_pages<T: DeserializeOwned>(
+        &self,
+        url_part: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>, AppError> {
+        let mut items = vec![];
+        let mut offset = 0;
+
+        loop {
+            let request = self
+                .request(Method::GET, url_part)
+                .await?
+                .query(query)
+                .query(&[("offset", offset), ("limit", PAGE_SIZE)]);
+
+            let page: RangedItems<T> =
+                send(request).await?.json().await.map_err(|e| {
+                    AppError::io_error(format!("Invalid recycle bin response: {e}"))
+                })?;
+
+            let total = page.range.total;
+            items.extend(page.items);
+
+            offset += PAGE_SIZE;
+            if offset >= total {
+                return Ok(items);
+            }
+        }
+    }
+
+    async fn request(&self, method: Method, url_part: &str) -> Result<RequestBuilder, AppError> {
+        Ok(self
+            .client
+            .request(method, self.dracoon.build_api_url(url_part))
+            .header(header::AUTHORIZATION, self.dracoon.get_auth_header().await?))
+    }
+}
+
+fn http_client(timeout: Duration) -> Result<Client, AppError> {
+    Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| AppError::ConnectionFailed(e.to_string()))
+}
+
+async fn send(request: RequestBuilder) -> Result<reqwest::Response, AppError> {
+    let res = request
+        .send()
+        .await
+        .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+
+    if res.status().is_success() {
+        return Ok(res);
+    }
+
+    let status = res.status();
+    let body = res.bytes().await.unwrap_or_default();
+
+    Err(AppError::DracoonError(
+        serde_json::from_slice(&body).unwrap_or_else(|_| {
+            DracoonErrorResponse::new(
+                i32::from(status.as_u16()),
+                status.canonical_reason().unwrap_or("Unknown error"),
+            )
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_times_out() {
+        // accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let client = http_client(Duration::from_millis(200)).unwrap();
+        let res = send(client.get(url)).await;
+
+        assert!(matches!(res, Err(AppError::ConnectionFailed(_))));
+        server.abort();
+    }
+}
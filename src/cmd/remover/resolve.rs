@@ -0,0 +1,112 @@
+use dco3::{auth::Connected, nodes::Node, Dracoon};
+use tracing::debug;
+
+use crate::cmd::{errors::AppError, models::RoomRef};
+
+use super::nodes::{get_all_nodes, node_path, search_rooms_by_name};
+
+/// Resolves a room reference to a room id.
+/// Absolute paths are walked from the root, plain names are searched on all levels.
+pub async fn resolve_room(dracoon: &Dracoon<Connected>, room: &RoomRef) -> Result<u64, AppError> {
+    let room = match room {
+        RoomRef::Id(id) => return Ok(*id),
+        RoomRef::Path(path) if path.starts_with('/') => walk_path(dracoon, path).await?,
+        RoomRef::Path(name) => search_name(dracoon, name).await?,
+    };
+
+    debug!("Resolved room {} to id {}", node_path(&room), room.id);
+
+    Ok(room.id)
+}
+
+async fn walk_path(dracoon: &Dracoon<Connected>, path: &str) -> Result<Node, AppError> {
+    let mut current: Option<Node> = None;
+
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        let rooms = get_all_nodes(dracoon.clone(), current.as_ref().map(|room| room.id)).await?;
+        current = Some(match_by_name(rooms, segment, path)?);
+    }
+
+    current.ok_or_else(|| AppError::InvalidArgument(format!("Invalid room path: {path}")))
+}
+
+async fn search_name(dracoon: &Dracoon<Connected>, name: &str) -> Result<Node, AppError> {
+    let rooms = search_rooms_by_name(dracoon, name).await?;
+
+    match_by_name(rooms, name, name)
+}
+
+// exact name match wins, otherwise a single case-insensitive match
+fn match_by_name(rooms: Vec<Node>, name: &str, path: &str) -> Result<Node, AppError> {
+    let (exact, other): (Vec<_>, Vec<_>) = rooms.into_iter().partition(|room| room.name == name);
+
+    let candidates = if exact.is_empty() {
+        other
+            .into_iter()
+            .filter(|room| room.name.eq_ignore_ascii_case(name))
+            .collect()
+    } else {
+        exact
+    };
+
+    match candidates.len() {
+        0 => Err(AppError::InvalidArgument(format!("Room not found: {path}"))),
+        1 => Ok(candidates.into_iter().next().expect("one candidate")),
+        _ => Err(AppError::InvalidArgument(format!(
+            "Room '{path}' is ambiguous - use the full path or the room id:\n{}",
+            candidates
+                .iter()
+                .map(|room| format!("  ► {} ({})", node_path(room), room.id))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::room as room_fixture;
+
+    fn room(id: u64, name: &str, parent_path: &str) -> Node {
+        room_fixture(
+            id,
+            serde_json::json!({ "name": name, "parentPath": parent_path }),
+        )
+    }
+
+    #[test]
+    fn test_room_ref_parse() {
+        assert_eq!("149".parse::<RoomRef>().unwrap(), RoomRef::Id(149));
+        assert_eq!(
+            "/Clients/ACME".parse::<RoomRef>().unwrap(),
+            RoomRef::Path("/Clients/ACME".to_string())
+        );
+    }
+
+    #[test]
+    fn test_match_by_name_exact_wins() {
+        let rooms = vec![room(1, "acme", "/"), room(2, "ACME", "/")];
+        assert_eq!(match_by_name(rooms, "ACME", "ACME").unwrap().id, 2);
+    }
+
+    #[test]
+    fn test_match_by_name_ambiguous() {
+        let rooms = vec![room(1, "ACME", "/Clients/"), room(2, "ACME", "/Archive/")];
+        let err = match_by_name(rooms, "ACME", "ACME").unwrap_err();
+
+        assert_eq!(
+            err,
+            AppError::InvalidArgument(
+                "Room 'ACME' is ambiguous - use the full path or the room id:\n  ► /Clients/ACME (1)\n  ► /Archive/ACME (2)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_match_by_name_not_found() {
+        let rooms = vec![room(1, "ACME", "/")];
+        assert!(match_by_name(rooms, "Other", "/Other").is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use dco3::{auth::Connected, Dracoon};
+use tracing::warn;
+
+use crate::cmd::errors::AppError;
+
+pub const USER_MANAGER: &str = "USER_MANAGER";
+pub const GROUP_MANAGER: &str = "GROUP_MANAGER";
+pub const LOG_AUDITOR: &str = "LOG_AUDITOR";
+
+/// Checks that the CLI user holds all given roles - fails with the first missing role
+pub async fn require_roles(dracoon: &Dracoon<Connected>, roles: &[&str]) -> Result<(), AppError> {
+    let user = dracoon.get_user_info().await?;
+
+    let missing = roles.iter().find(|role| {
+        !user
+            .user_roles
+            .items
+            .iter()
+            .any(|user_role| user_role.name == **role)
+    });
+
+    match missing {
+        Some(role) => {
+            warn!("User {} is missing role {}", user.user_name, role);
+            Err(AppError::MissingRole(role.to_string()))
+        }
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,538 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use console::Term;
+use dco3::{
+    auth::Connected,
+    models::ListAllParamsBuilder,
+    nodes::{Node, NodeType, UserInfo},
+    shares::{
+        DownloadShare, DownloadSharesFilter, DownloadSharesSortBy, UpdateDownloadShareRequest,
+        UpdateUploadShareRequest, UploadShare, UploadSharesFilter, UploadSharesSortBy,
+    },
+    DownloadShares, Dracoon, ListAllParams, ObjectExpiration, SortOrder, SortQuery, UploadShares,
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use tracing::{info, info_span, instrument};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{CredentialOptions, OutputFormat, PasswordAuth, ShareKind, ShareOptions, UserRef},
+    utils::dates::to_local_string,
+};
+
+use super::{
+    collect_rooms,
+    confirm::confirm_bulk,
+    get_all, get_parent_ids, logged_update,
+    managed::collect_managed_rooms,
+    nodes::{node_path, search_content_pages},
+    BulkReport, ExpirationUpdate, LIST_PAGE_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ShareType {
+    Download,
+    Upload,
+}
+
+// common fields of download and upload shares
+#[derive(Debug, Clone)]
+struct Share {
+    id: u64,
+    share_type: ShareType,
+    name: String,
+    path: String,
+    created_by: UserInfo,
+    expire_at: Option<DateTime<Utc>>,
+}
+
+impl From<DownloadShare> for Share {
+    fn from(share: DownloadShare) -> Self {
+        Self {
+            id: share.id,
+            share_type: ShareType::Download,
+            name: share.name,
+            path: share.node_path.unwrap_or_default(),
+            created_by: share.created_by,
+            expire_at: share.expire_at,
+        }
+    }
+}
+
+impl From<UploadShare> for Share {
+    fn from(share: UploadShare) -> Self {
+        Self {
+            id: share.id,
+            share_type: ShareType::Upload,
+            name: share.name,
+            path: share.target_path.unwrap_or_default(),
+            created_by: share.created_by,
+            expire_at: share.expire_at,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ShareChange {
+    id: u64,
+    #[serde(rename = "type")]
+    share_type: ShareType,
+    name: String,
+    path: String,
+    created_by: String,
+    expire_at: DateTime<Utc>,
+    // `None` removes the expiration
+    new_expire_at: Option<DateTime<Utc>>,
+}
+
+// shares in scope of the collected rooms and matching the filters
+struct ShareFilter {
+    room_paths: Vec<String>,
+    created_by: Option<UserRef>,
+}
+
+impl ShareFilter {
+    fn in_rooms(&self, path: &str) -> bool {
+        self.room_paths.iter().any(|room_path| {
+            path.strip_prefix(room_path.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    fn created_by(&self, user: &UserInfo) -> bool {
+        match &self.created_by {
+            None => true,
+            Some(UserRef::Id(id)) => user.id == *id,
+            Some(UserRef::Name(name)) => user
+                .user_name
+                .as_deref()
+                .is_some_and(|user_name| user_name.eq_ignore_ascii_case(name)),
+        }
+    }
+}
+
+fn select_shares(
+    shares: Vec<Share>,
+    filter: &ShareFilter,
+    update: ExpirationUpdate,
+) -> Vec<ShareChange> {
+    shares
+        .into_iter()
+        .filter(|share| filter.created_by(&share.created_by) && filter.in_rooms(&share.path))
+        .filter_map(|share| {
+            let (expire_at, new_expire_at) = update.select(share.expire_at)?;
+
+            Some(ShareChange {
+                id: share.id,
+                share_type: share.share_type,
+                name: share.name,
+                path: share.path,
+                created_by: share
+                    .created_by
+                    .user_name
+                    .unwrap_or_else(|| share.created_by.id.to_string()),
+                expire_at,
+                new_expire_at,
+            })
+        })
+        .collect()
+}
+
+fn print_changes(term: &Term, shares: &[ShareChange], rooms: usize) -> Result<(), AppError> {
+    term.write_line(&format!(
+        "► Shares to update in {} rooms: {}",
+        rooms,
+        shares.len()
+    ))?;
+
+    for share in shares {
+        term.write_line(&format!(
+            "  ▶︎ {:?} share {} ({}) on {} by {}: {} → {}",
+            share.share_type,
+            share.name,
+            share.id,
+            share.path,
+            share.created_by,
+            to_local_string(&share.expire_at),
+            share
+                .new_expire_at
+                .map_or_else(|| "none".to_string(), |date| to_local_string(&date))
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Removes or extends the expiration of the download and upload shares in the collected rooms
+#[instrument(name = "shares", skip(term, auth, credentials))]
+pub async fn handle_shares(
+    term: Term,
+    base_url: String,
+    opts: ShareOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = update_shares(&term, &dracoon, opts).await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+async fn update_shares(
+    term: &Term,
+    dracoon: &Dracoon<Connected>,
+    opts: ShareOptions,
+) -> Result<(), AppError> {
+    let rooms = if opts.all_managed {
+        collect_managed_rooms(dracoon.clone()).await?
+    } else {
+        let parent_ids = get_parent_ids(dracoon, term, &opts.rooms).await?;
+        collect_rooms(dracoon.clone(), &parent_ids).await?
+    };
+
+    let filter = ShareFilter {
+        room_paths: rooms.iter().map(node_path).collect(),
+        created_by: opts.created_by.clone(),
+    };
+    let update = ExpirationUpdate {
+        change: opts.change,
+        expires_before: opts.expires_before,
+        now: Utc::now(),
+    };
+
+    let shares = list_shares(dracoon, opts.kind, &rooms).await?;
+    info!("Found {} shares visible to the user", shares.len());
+
+    let report = BulkReport::new(
+        "shares",
+        opts.dry_run,
+        select_shares(shares, &filter, update),
+    );
+
+    if report.changes.is_empty() {
+        return match opts.format {
+            OutputFormat::Text => Ok(term.write_line("► No shares to update.")?),
+            OutputFormat::Json => report.print(term, opts.format),
+        };
+    }
+
+    if opts.format == OutputFormat::Text {
+        print_changes(term, &report.changes, rooms.len())?;
+    }
+
+    if opts.dry_run {
+        info!("Dry run - {} shares not modified", report.changes.len());
+        return report.print(term, opts.format);
+    }
+
+    let prompt = format!("Update the expiration of {} shares?", report.changes.len());
+    if !confirm_bulk(term, prompt, opts.yes)? {
+        return Err(AppError::Aborted("no shares modified".to_string()));
+    }
+
+    report
+        .apply(term, opts.format, |share| update_share(dracoon, share))
+        .await
+}
+
+async fn update_share(dracoon: &Dracoon<Connected>, share: &ShareChange) -> Result<(), AppError> {
+    let span = info_span!(
+        "update_share",
+        share_id = share.id,
+        share_type = ?share.share_type
+    );
+
+    let expiration = share
+        .new_expire_at
+        .map_or_else(ObjectExpiration::default, ObjectExpiration::new);
+
+    let update = async {
+        match share.share_type {
+            ShareType::Download => {
+                let update = UpdateDownloadShareRequest::builder()
+                    .with_expiration(expiration)
+                    .build();
+                dracoon
+                    .shares()
+                    .update_download_share(share.id, update)
+                    .await
+                    .map(|_| ())
+            }
+            ShareType::Upload => {
+                let update = UpdateUploadShareRequest::builder()
+                    .with_expiration(expiration)
+                    .build();
+                dracoon
+                    .shares()
+                    .update_upload_share(share.id, update)
+                    .await
+                    .map(|_| ())
+            }
+        }
+    };
+
+    Ok(logged_update(span, "Share expiration", update).await?)
+}
+
+// share counts missing from a listing are not taken as zero -
+// upload shares (file requests) only target rooms and folders
+fn has_shares(node: &Node, kind: ShareKind) -> bool {
+    let download = kind != ShareKind::Upload && node.cnt_download_shares != Some(0);
+    let upload = kind != ShareKind::Download
+        && node.node_type != NodeType::File
+        && node.cnt_upload_shares != Some(0);
+
+    download || upload
+}
+
+// rooms, files and folders in the collected rooms that carry shares
+async fn list_shared_nodes(
+    dracoon: &Dracoon<Connected>,
+    kind: ShareKind,
+    rooms: &[Node],
+) -> Result<Vec<Node>, AppError> {
+    // the search below a room covers its sub rooms as well
+    let room_ids = rooms.iter().map(|room| room.id).collect::<HashSet<_>>();
+    let top_rooms = rooms
+        .iter()
+        .filter(|room| !room.parent_id.is_some_and(|id| room_ids.contains(&id)));
+
+    let contents = stream::iter(top_rooms)
+        .map(|room| search_content_pages(dracoon, room.id).try_concat())
+        .buffer_unordered(5)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    Ok(rooms
+        .iter()
+        .cloned()
+        .chain(contents.into_iter().flatten())
+        .filter(|node| has_shares(node, kind))
+        .collect())
+}
+
+#[instrument(skip_all, fields(rooms = rooms.len()))]
+async fn list_shares(
+    dracoon: &Dracoon<Connected>,
+    kind: ShareKind,
+    rooms: &[Node],
+) -> Result<Vec<Share>, AppError> {
+    let mut shares = fetch_shares(dracoon, kind, None).await?;
+
+    // the user's own list misses the shares of other users on the nodes
+    let nodes = list_shared_nodes(dracoon, kind, rooms).await?;
+    info!("Found {} nodes with shares in the rooms", nodes.len());
+
+    let node_shares = stream::iter(&nodes)
+        .map(|node| fetch_shares(dracoon, kind, Some(node.id)))
+        .buffer_unordered(10)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    // own shares on the nodes are in both lists
+    let mut seen = shares
+        .iter()
+        .map(|share| (share.share_type, share.id))
+        .collect::<HashSet<_>>();
+    shares.extend(
+        node_shares
+            .into_iter()
+            .flatten()
+            .filter(|share| seen.insert((share.share_type, share.id))),
+    );
+
+    Ok(shares)
+}
+
+// all shares of the user - or all shares on the node with `node_id`
+async fn fetch_shares(
+    dracoon: &Dracoon<Connected>,
+    kind: ShareKind,
+    node_id: Option<u64>,
+) -> Result<Vec<Share>, AppError> {
+    let mut shares = vec![];
+
+    if kind != ShareKind::Upload {
+        let download_shares = get_all(|offset| async move {
+            let params = share_params(offset, DownloadSharesSortBy::created_at(SortOrder::Asc));
+            let params = match node_id {
+                Some(node_id) => params.with_filter(DownloadSharesFilter::node_id_equals(node_id)),
+                None => params,
+            };
+            dracoon
+                .shares()
+                .get_download_shares(Some(params.build()))
+                .await
+        })
+        .await?;
+        shares.extend(download_shares.into_iter().map(Share::from));
+    }
+
+    if kind != ShareKind::Download {
+        let upload_shares = get_all(|offset| async move {
+            let params = share_params(offset, UploadSharesSortBy::created_at(SortOrder::Asc));
+            let params = match node_id {
+                Some(node_id) => params.with_filter(UploadSharesFilter::target_id_equals(node_id)),
+                None => params,
+            };
+            dracoon
+                .shares()
+                .get_upload_shares(Some(params.build()))
+                .await
+        })
+        .await?;
+        shares.extend(upload_shares.into_iter().map(Share::from));
+    }
+
+    Ok(shares)
+}
+
+fn share_params(offset: u64, sort: impl Into<Box<dyn SortQuery>>) -> ListAllParamsBuilder {
+    ListAllParams::builder()
+        .with_offset(offset)
+        .with_limit(LIST_PAGE_SIZE)
+        .with_sort(sort)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::models::ExpirationChange;
+    use crate::cmd::remover::fixtures::{from_json, room};
+
+    fn share(id: u64, path: &str, user_name: &str) -> Share {
+        Share {
+            id,
+            share_type: ShareType::Download,
+            name: format!("share-{id}"),
+            path: path.to_string(),
+            created_by: from_json(serde_json::json!({
+                "id": id, "userType": "internal", "userName": user_name, "avatarUuid": "x"
+            })),
+            expire_at: Some("2024-05-10T00:00:00Z".parse().unwrap()),
+        }
+    }
+
+    fn selected(shares: Vec<Share>, filter: &ShareFilter) -> Vec<u64> {
+        let update = ExpirationUpdate {
+            change: ExpirationChange::Remove,
+            expires_before: None,
+            now: "2024-05-01T00:00:00Z".parse().unwrap(),
+        };
+
+        select_shares(shares, filter, update)
+            .into_iter()
+            .map(|change| change.id)
+            .collect()
+    }
+
+    fn in_rooms(room_paths: &[&str]) -> ShareFilter {
+        ShareFilter {
+            room_paths: room_paths.iter().map(|path| path.to_string()).collect(),
+            created_by: None,
+        }
+    }
+
+    #[test]
+    fn test_select_shares_in_room_paths() {
+        let shares = vec![
+            // the room itself
+            share(1, "/Clients/ACME", "jdoe"),
+            share(2, "/Clients/ACME/report.pdf", "jdoe"),
+            share(3, "/Clients/ACME/Projects/2024/plan.pdf", "jdoe"),
+            // same prefix, different room
+            share(4, "/Clients/ACME-Old/a.pdf", "jdoe"),
+            share(5, "/Clients/ACMEcorp", "jdoe"),
+            // parent of the room
+            share(6, "/Clients", "jdoe"),
+            share(7, "/Other/Clients/ACME/b.pdf", "jdoe"),
+        ];
+
+        assert_eq!(
+            selected(shares, &in_rooms(&["/Clients/ACME"])),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_select_shares_in_several_rooms() {
+        let shares = vec![
+            share(1, "/Clients/ACME/a.pdf", "jdoe"),
+            share(2, "/Clients/Initech/b.pdf", "jdoe"),
+            share(3, "/Clients/Globex/c.pdf", "jdoe"),
+        ];
+
+        assert_eq!(
+            selected(shares, &in_rooms(&["/Clients/ACME", "/Clients/Initech"])),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_select_shares_of_other_users_on_files() {
+        let shares = vec![share(8, "/Clients/ACME/Projects/plan.pdf", "max")];
+
+        let changes = select_shares(
+            shares,
+            &in_rooms(&["/Clients/ACME"]),
+            ExpirationUpdate {
+                change: ExpirationChange::Remove,
+                expires_before: None,
+                now: "2024-05-01T00:00:00Z".parse().unwrap(),
+            },
+        );
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].created_by, "max");
+        assert_eq!(changes[0].path, "/Clients/ACME/Projects/plan.pdf");
+    }
+
+    #[test]
+    fn test_select_shares_created_by() {
+        let shares = || {
+            vec![
+                share(1, "/Clients/ACME/a.pdf", "jdoe"),
+                share(2, "/Clients/ACME/b.pdf", "max"),
+            ]
+        };
+
+        let filter = ShareFilter {
+            created_by: Some(UserRef::Name("MAX".to_string())),
+            ..in_rooms(&["/Clients/ACME"])
+        };
+        assert_eq!(selected(shares(), &filter), vec![2]);
+
+        // the fixture's user id is the share id
+        let filter = ShareFilter {
+            created_by: Some(UserRef::Id(1)),
+            ..in_rooms(&["/Clients/ACME"])
+        };
+        assert_eq!(selected(shares(), &filter), vec![1]);
+    }
+
+    #[test]
+    fn test_has_shares() {
+        let file = |fields| room(7, fields);
+
+        let shared = file(serde_json::json!({ "type": "file", "cntDownloadShares": 1 }));
+        assert!(has_shares(&shared, ShareKind::Download));
+        assert!(has_shares(&shared, ShareKind::All));
+        // files carry no upload shares
+        assert!(!has_shares(&shared, ShareKind::Upload));
+
+        let unshared = file(serde_json::json!({ "type": "file", "cntDownloadShares": 0 }));
+        assert!(!has_shares(&unshared, ShareKind::All));
+
+        // unknown counts are queried
+        let folder = file(serde_json::json!({ "type": "folder" }));
+        assert!(has_shares(&folder, ShareKind::Upload));
+    }
+}
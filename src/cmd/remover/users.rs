@@ -0,0 +1,450 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use console::Term;
+use dco3::{
+    auth::Connected,
+    groups::{Group, GroupsSortBy, UpdateGroupRequest},
+    nodes::Node,
+    users::{UpdateUserRequest, UserItem, UsersSortBy},
+    Dracoon, Groups, ListAllParams, ObjectExpiration, Rooms, SortOrder, SortQuery, Users,
+};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use tracing::{info, info_span, instrument};
+
+use crate::cmd::{
+    errors::AppError,
+    init_dracoon,
+    models::{AccountKind, AccountOptions, CredentialOptions, OutputFormat, PasswordAuth},
+    utils::dates::to_local_string,
+};
+
+use super::{
+    collect_rooms,
+    confirm::confirm_bulk,
+    get_all, get_parent_ids, logged_update,
+    roles::{require_roles, GROUP_MANAGER, USER_MANAGER},
+    BulkReport, ExpirationUpdate, LIST_PAGE_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum AccountType {
+    User,
+    Group,
+}
+
+// common fields of users and groups
+#[derive(Debug, Clone)]
+struct Account {
+    id: u64,
+    account_type: AccountType,
+    name: String,
+    expire_at: Option<DateTime<Utc>>,
+}
+
+impl From<UserItem> for Account {
+    fn from(user: UserItem) -> Self {
+        Self {
+            id: user.id,
+            account_type: AccountType::User,
+            name: format!(
+                "{} ({} {})",
+                user.user_name, user.first_name, user.last_name
+            ),
+            expire_at: user.expire_at,
+        }
+    }
+}
+
+impl From<Group> for Account {
+    fn from(group: Group) -> Self {
+        Self {
+            id: group.id,
+            account_type: AccountType::Group,
+            name: group.name,
+            expire_at: group.expire_at,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct AccountChange {
+    id: u64,
+    #[serde(rename = "type")]
+    account_type: AccountType,
+    name: String,
+    expire_at: DateTime<Utc>,
+    // `None` removes the expiration
+    new_expire_at: Option<DateTime<Utc>>,
+}
+
+// users and groups granted access to the collected rooms
+#[derive(Debug, Default)]
+struct RoomMembers {
+    user_ids: HashSet<u64>,
+    group_ids: HashSet<u64>,
+}
+
+struct AccountFilter {
+    // `None` if no rooms were given - all accounts
+    members: Option<RoomMembers>,
+}
+
+impl AccountFilter {
+    fn is_member(&self, account: &Account) -> bool {
+        self.members
+            .as_ref()
+            .is_none_or(|members| match account.account_type {
+                AccountType::User => members.user_ids.contains(&account.id),
+                AccountType::Group => members.group_ids.contains(&account.id),
+            })
+    }
+}
+
+fn select_accounts(
+    accounts: Vec<Account>,
+    filter: &AccountFilter,
+    update: ExpirationUpdate,
+) -> Vec<AccountChange> {
+    accounts
+        .into_iter()
+        .filter(|account| filter.is_member(account))
+        .filter_map(|account| {
+            let (expire_at, new_expire_at) = update.select(account.expire_at)?;
+
+            Some(AccountChange {
+                id: account.id,
+                account_type: account.account_type,
+                name: account.name,
+                expire_at,
+                new_expire_at,
+            })
+        })
+        .collect()
+}
+
+fn print_changes(term: &Term, accounts: &[AccountChange]) -> Result<(), AppError> {
+    term.write_line(&format!("► Accounts to update: {}", accounts.len()))?;
+
+    for account in accounts {
+        term.write_line(&format!(
+            "  ▶︎ {:?} {} ({}): {} → {}",
+            account.account_type,
+            account.name,
+            account.id,
+            to_local_string(&account.expire_at),
+            account
+                .new_expire_at
+                .map_or_else(|| "none".to_string(), |date| to_local_string(&date))
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Removes or extends the expiration of user and group accounts - optionally only members of the collected rooms
+#[instrument(name = "users", skip(term, auth, credentials))]
+pub async fn handle_users(
+    term: Term,
+    base_url: String,
+    opts: AccountOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    let res = update_accounts(&term, &dracoon, opts).await;
+
+    session.persist_refresh_token().await;
+
+    res
+}
+
+async fn update_accounts(
+    term: &Term,
+    dracoon: &Dracoon<Connected>,
+    opts: AccountOptions,
+) -> Result<(), AppError> {
+    let required_roles = match opts.kind {
+        AccountKind::All => vec![USER_MANAGER, GROUP_MANAGER],
+        AccountKind::Users => vec![USER_MANAGER],
+        AccountKind::Groups => vec![GROUP_MANAGER],
+    };
+    require_roles(dracoon, &required_roles).await?;
+
+    let members = if opts.rooms.is_empty() {
+        None
+    } else {
+        let parent_ids = get_parent_ids(dracoon, term, &opts.rooms).await?;
+        let rooms = collect_rooms(dracoon.clone(), &parent_ids).await?;
+        Some(collect_members(dracoon, &rooms).await?)
+    };
+
+    let filter = AccountFilter { members };
+    let update = ExpirationUpdate {
+        change: opts.change,
+        expires_before: opts.expires_before,
+        now: Utc::now(),
+    };
+
+    let accounts = list_accounts(dracoon, opts.kind).await?;
+    info!("Found {} accounts", accounts.len());
+
+    let report = BulkReport::new(
+        "accounts",
+        opts.dry_run,
+        select_accounts(accounts, &filter, update),
+    );
+
+    if report.changes.is_empty() {
+        return match opts.format {
+            OutputFormat::Text => Ok(term.write_line("► No accounts to update.")?),
+            OutputFormat::Json => report.print(term, opts.format),
+        };
+    }
+
+    if opts.format == OutputFormat::Text {
+        print_changes(term, &report.changes)?;
+    }
+
+    if opts.dry_run {
+        info!("Dry run - {} accounts not modified", report.changes.len());
+        return report.print(term, opts.format);
+    }
+
+    let prompt = format!(
+        "Update the expiration of {} accounts?",
+        report.changes.len()
+    );
+    if !confirm_bulk(term, prompt, opts.yes)? {
+        return Err(AppError::Aborted("no accounts modified".to_string()));
+    }
+
+    report
+        .apply(term, opts.format, |account| {
+            update_account(dracoon, account)
+        })
+        .await
+}
+
+async fn update_account(
+    dracoon: &Dracoon<Connected>,
+    account: &AccountChange,
+) -> Result<(), AppError> {
+    let span = info_span!(
+        "update_account",
+        account_id = account.id,
+        account_type = ?account.account_type
+    );
+
+    let expiration = account
+        .new_expire_at
+        .map_or_else(ObjectExpiration::default, ObjectExpiration::new);
+
+    let update = async {
+        match account.account_type {
+            AccountType::User => {
+                let update = UpdateUserRequest::builder()
+                    .with_expiration(expiration)
+                    .build();
+                dracoon
+                    .users()
+                    .update_user(account.id, update)
+                    .await
+                    .map(|_| ())
+            }
+            AccountType::Group => dracoon
+                .groups()
+                .update_group(account.id, UpdateGroupRequest::expiration(expiration))
+                .await
+                .map(|_| ()),
+        }
+    };
+
+    Ok(logged_update(span, "Account expiration", update).await?)
+}
+
+// granted users and groups of the rooms (10 concurrent rooms)
+#[instrument(skip(dracoon, rooms), fields(rooms = rooms.len()))]
+async fn collect_members(
+    dracoon: &Dracoon<Connected>,
+    rooms: &[Node],
+) -> Result<RoomMembers, AppError> {
+    let mut room_stream = stream::iter(rooms)
+        .map(|room| async move {
+            let users = get_all(|offset| async move {
+                dracoon
+                    .nodes()
+                    .get_room_users(room.id, Some(list_params(offset, None)))
+                    .await
+            })
+            .await?;
+            let groups = get_all(|offset| async move {
+                dracoon
+                    .nodes()
+                    .get_room_groups(room.id, Some(list_params(offset, None)))
+                    .await
+            })
+            .await?;
+
+            Ok::<_, AppError>((users, groups))
+        })
+        .buffer_unordered(10);
+
+    let mut members = RoomMembers::default();
+    while let Some(result) = room_stream.next().await {
+        let (users, groups) = result?;
+        members.user_ids.extend(
+            users
+                .into_iter()
+                .filter(|user| user.is_granted)
+                .filter_map(|user| u64::try_from(user.user_info.id).ok()),
+        );
+        members.group_ids.extend(
+            groups
+                .into_iter()
+                .filter(|group| group.is_granted)
+                .map(|group| group.id),
+        );
+    }
+
+    info!(
+        users = members.user_ids.len(),
+        groups = members.group_ids.len(),
+        "Room members collected"
+    );
+
+    Ok(members)
+}
+
+#[instrument(skip(dracoon))]
+async fn list_accounts(
+    dracoon: &Dracoon<Connected>,
+    kind: AccountKind,
+) -> Result<Vec<Account>, AppError> {
+    let mut accounts = vec![];
+
+    if kind != AccountKind::Groups {
+        let users = get_all(|offset| async move {
+            dracoon
+                .users()
+                .get_users(
+                    Some(list_params(
+                        offset,
+                        Some(UsersSortBy::user_name(SortOrder::Asc).into()),
+                    )),
+                    None,
+                    None,
+                )
+                .await
+        })
+        .await?;
+        accounts.extend(users.into_iter().map(Account::from));
+    }
+
+    if kind != AccountKind::Users {
+        let groups = get_all(|offset| async move {
+            dracoon
+                .groups()
+                .get_groups(Some(list_params(
+                    offset,
+                    Some(GroupsSortBy::name(SortOrder::Asc).into()),
+                )))
+                .await
+        })
+        .await?;
+        accounts.extend(groups.into_iter().map(Account::from));
+    }
+
+    Ok(accounts)
+}
+
+fn list_params(offset: u64, sort: Option<Box<dyn SortQuery>>) -> ListAllParams {
+    let params = ListAllParams::builder()
+        .with_offset(offset)
+        .with_limit(LIST_PAGE_SIZE);
+
+    match sort {
+        Some(sort) => params.with_sort(sort).build(),
+        None => params.build(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::models::ExpirationChange;
+
+    fn account(id: u64, account_type: AccountType) -> Account {
+        Account {
+            id,
+            account_type,
+            name: format!("account-{id}"),
+            expire_at: Some("2024-05-10T00:00:00Z".parse().unwrap()),
+        }
+    }
+
+    fn selected(accounts: Vec<Account>, filter: &AccountFilter) -> Vec<(AccountType, u64)> {
+        let update = ExpirationUpdate {
+            change: ExpirationChange::Remove,
+            expires_before: None,
+            now: "2024-05-01T00:00:00Z".parse().unwrap(),
+        };
+
+        select_accounts(accounts, filter, update)
+            .into_iter()
+            .map(|change| (change.account_type, change.id))
+            .collect()
+    }
+
+    #[test]
+    fn test_select_accounts_without_rooms() {
+        let accounts = vec![
+            account(1, AccountType::User),
+            account(1, AccountType::Group),
+        ];
+
+        assert_eq!(
+            selected(accounts, &AccountFilter { members: None }),
+            vec![(AccountType::User, 1), (AccountType::Group, 1)]
+        );
+    }
+
+    #[test]
+    fn test_select_accounts_matches_user_and_group_ids_separately() {
+        // users and groups share the id range: user 1 and group 1 are different accounts
+        let accounts = vec![
+            account(1, AccountType::User),
+            account(2, AccountType::User),
+            account(1, AccountType::Group),
+            account(2, AccountType::Group),
+        ];
+        let filter = AccountFilter {
+            members: Some(RoomMembers {
+                user_ids: HashSet::from([1]),
+                group_ids: HashSet::from([2]),
+            }),
+        };
+
+        assert_eq!(
+            selected(accounts, &filter),
+            vec![(AccountType::User, 1), (AccountType::Group, 2)]
+        );
+    }
+
+    #[test]
+    fn test_select_accounts_without_room_members() {
+        let accounts = vec![
+            account(3, AccountType::User),
+            account(3, AccountType::Group),
+        ];
+        let filter = AccountFilter {
+            members: Some(RoomMembers::default()),
+        };
+
+        assert!(selected(accounts, &filter).is_empty());
+    }
+}
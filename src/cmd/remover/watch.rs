@@ -0,0 +1,341 @@
+use std::{collections::HashSet, future::Future, pin::Pin, time::Duration};
+
+use console::Term;
+use dco3::{
+    auth::Connected,
+    nodes::{Node, RoomPolicies},
+    Dracoon, DracoonClientError,
+};
+use tokio::time::{Interval, MissedTickBehavior};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::cmd::{
+    errors::AppError,
+    get_error_message, init_dracoon,
+    models::{CredentialOptions, PasswordAuth, WatchOptions},
+    utils::strings::format_error_message,
+};
+
+use super::{
+    collect_rooms,
+    managed::collect_managed_rooms,
+    nodes::{get_room_policies, node_path},
+    permissions::has_manage_permission,
+    resolve::resolve_room,
+    revert_expiration_date,
+};
+
+// outcome of a single scan
+#[derive(Debug, Default)]
+struct CycleReport {
+    checked: usize,
+    corrected: usize,
+    failed: usize,
+    unmanaged: Vec<Node>,
+}
+
+// scan interval - shutdown is only checked between scans, a running scan is always finished
+struct Ticker<S> {
+    interval: Interval,
+    shutdown: Pin<Box<S>>,
+}
+
+impl<S: Future<Output = ()>> Ticker<S> {
+    fn new(period: Duration, shutdown: S) -> Self {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            interval,
+            shutdown: Box::pin(shutdown),
+        }
+    }
+
+    // `false` once shutdown was requested
+    async fn tick(&mut self) -> bool {
+        tokio::select! {
+            biased;
+            () = &mut self.shutdown => false,
+            _ = self.interval.tick() => true,
+        }
+    }
+}
+
+/// Re-scans the watched room trees at a fixed interval and removes expiration policies
+/// that were (re-)added since the last scan. Runs until Ctrl+C / SIGTERM.
+#[instrument(name = "watch", skip(term, auth, credentials))]
+pub async fn handle_watch(
+    term: Term,
+    base_url: String,
+    opts: WatchOptions,
+    auth: Option<PasswordAuth>,
+    credentials: CredentialOptions,
+) -> Result<(), AppError> {
+    let session = init_dracoon(&base_url, auth, &credentials, true).await?;
+    let dracoon = session.dracoon.clone();
+
+    // room ids are stable - resolve paths once
+    let parent_ids = if opts.all_managed {
+        None
+    } else {
+        let mut parent_ids = vec![];
+        for room in &opts.rooms {
+            parent_ids.push(resolve_room(&dracoon, room).await?);
+        }
+        Some(parent_ids)
+    };
+
+    let watched = parent_ids.as_ref().map_or_else(
+        || "all rooms with manage permission".to_string(),
+        |ids| format!("rooms below {ids:?}"),
+    );
+    info!("Watching {} every {}s", watched, opts.interval);
+    term.write_line(&format!(
+        "► Watching {watched} every {}s - press Ctrl+C to stop.",
+        opts.interval
+    ))?;
+
+    let mut ticker = Ticker::new(Duration::from_secs(opts.interval), shutdown_signal());
+    let mut warned = HashSet::new();
+
+    while ticker.tick().await {
+        match enforce_policy(&dracoon, parent_ids.as_deref()).await {
+            Ok(report) => {
+                log_unmanaged(&term, &report.unmanaged, &mut warned)?;
+
+                info!(
+                    checked = report.checked,
+                    corrected = report.corrected,
+                    failed = report.failed,
+                    "Scan finished"
+                );
+                term.write_line(&format!(
+                    "► {} - checked {} rooms, corrected {}, failed {}",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    report.checked,
+                    report.corrected,
+                    report.failed
+                ))?;
+            }
+            // keep watching - the next scan may succeed (e.g. after a network outage)
+            Err(e) => {
+                error!("Scan failed: {}", e);
+                term.write_line(&format_error_message(&get_error_message(&e)))?;
+            }
+        }
+
+        session.persist_refresh_token().await;
+    }
+
+    info!("Watch stopped");
+    term.write_line("► Watch stopped.")?;
+
+    Ok(())
+}
+
+#[instrument(skip(dracoon))]
+async fn enforce_policy(
+    dracoon: &Dracoon<Connected>,
+    parent_ids: Option<&[u64]>,
+) -> Result<CycleReport, AppError> {
+    let rooms = match parent_ids {
+        Some(parent_ids) => collect_rooms(dracoon.clone(), parent_ids).await?,
+        None => collect_managed_rooms(dracoon.clone()).await?,
+    };
+
+    // unattended: rooms without manage permission are skipped (logged once by the caller)
+    let (rooms, unmanaged): (Vec<_>, Vec<_>) = rooms.into_iter().partition(has_manage_permission);
+    if !unmanaged.is_empty() {
+        debug!(
+            "Skipping {} rooms without manage permission",
+            unmanaged.len()
+        );
+    }
+
+    let mut report = CycleReport {
+        checked: rooms.len(),
+        unmanaged,
+        ..Default::default()
+    };
+
+    let policies = get_room_policies(dracoon, rooms).await;
+    let drifted = find_drifted_rooms(policies, &mut report);
+
+    if drifted.is_empty() {
+        return Ok(report);
+    }
+
+    let (count, res) = revert_expiration_date(dracoon.clone(), drifted).await;
+    report.corrected = count.updated;
+    report.failed += count.failed;
+
+    match res {
+        Ok(()) | Err(AppError::PartialSuccess { .. }) => Ok(report),
+        Err(e) => Err(e),
+    }
+}
+
+// returns the ids of all rooms with an expiration policy
+fn find_drifted_rooms(
+    policies: Vec<(Node, Result<RoomPolicies, DracoonClientError>)>,
+    report: &mut CycleReport,
+) -> Vec<u64> {
+    let mut drifted = vec![];
+
+    for (room, policies) in policies {
+        match policies {
+            Ok(policies) if policies.default_expiration_period != 0 => {
+                info!(
+                    room_id = room.id,
+                    expiration_period = policies.default_expiration_period,
+                    "Expiration policy drifted on room {}",
+                    room.name
+                );
+                drifted.push(room.id);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Failed to fetch policies of room {}: {}", room.id, e);
+                report.failed += 1;
+            }
+        }
+    }
+
+    drifted
+}
+
+// rooms without manage permission are reported on the first scan they show up in
+fn log_unmanaged(
+    term: &Term,
+    unmanaged: &[Node],
+    warned: &mut HashSet<u64>,
+) -> Result<(), AppError> {
+    let new = unmanaged
+        .iter()
+        .filter(|room| warned.insert(room.id))
+        .collect::<Vec<_>>();
+
+    if new.is_empty() {
+        return Ok(());
+    }
+
+    term.write_line(&format_error_message(&format!(
+        "Missing room admin rights (manage permission) on {} rooms - skipped:",
+        new.len()
+    )))?;
+
+    for room in new {
+        warn!(
+            "Missing manage permission on room {} ({})",
+            room.id, room.name
+        );
+        term.write_line(&format!("  ► {} ({})", room.id, node_path(room)))?;
+    }
+
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut sigterm) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+            return;
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen for shutdown signal: {}", e);
+        // never resolve - the process can still be killed
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::remover::fixtures::{room as room_fixture, FileTerm};
+
+    fn room(id: u64) -> Node {
+        room_fixture(id, serde_json::json!({}))
+    }
+
+    fn policies(expiration_period: u64) -> Result<RoomPolicies, DracoonClientError> {
+        Ok(RoomPolicies {
+            default_expiration_period: expiration_period,
+            is_virus_protection_enabled: false,
+        })
+    }
+
+    #[test]
+    fn test_find_drifted_rooms() {
+        let mut report = CycleReport::default();
+
+        let drifted = find_drifted_rooms(
+            vec![
+                (room(1), policies(0)),
+                (room(2), policies(30)),
+                (
+                    room(3),
+                    Err(DracoonClientError::ConnectionFailed("timeout".into())),
+                ),
+            ],
+            &mut report,
+        );
+
+        assert_eq!(drifted, vec![2]);
+        assert_eq!(report.failed, 1);
+    }
+
+    #[test]
+    fn test_log_unmanaged_once() {
+        let file_term = FileTerm::new();
+        let term = &file_term.term;
+        let mut warned = HashSet::new();
+
+        log_unmanaged(term, &[room(1), room(2)], &mut warned).unwrap();
+        log_unmanaged(term, &[room(1), room(2)], &mut warned).unwrap();
+        log_unmanaged(term, &[room(2), room(3)], &mut warned).unwrap();
+
+        let output = file_term.output();
+        assert_eq!(output.matches("room-1").count(), 1);
+        assert_eq!(output.matches("room-2").count(), 1);
+        assert_eq!(output.matches("room-3").count(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ticker_scans_until_shutdown() {
+        let shutdown = tokio::time::sleep(Duration::from_millis(2500));
+        let mut ticker = Ticker::new(Duration::from_secs(1), shutdown);
+
+        let mut scans = 0;
+        while ticker.tick().await {
+            scans += 1;
+        }
+
+        // first scan immediately, then at 1s and 2s
+        assert_eq!(scans, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ticker_finishes_running_scan() {
+        let start = tokio::time::Instant::now();
+        let shutdown = tokio::time::sleep(Duration::from_millis(500));
+        let mut ticker = Ticker::new(Duration::from_secs(1), shutdown);
+
+        let mut scans = 0;
+        while ticker.tick().await {
+            // scan outlasts the shutdown signal
+            tokio::time::sleep(Duration::from_secs(3)).await;
+            scans += 1;
+        }
+
+        assert_eq!(scans, 1);
+        assert!(start.elapsed() >= Duration::from_secs(3));
+    }
+}
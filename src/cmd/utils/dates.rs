@@ -1,8 +1,76 @@
 use std::time::SystemTime;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
 
 #[allow(unused)]
 pub fn to_datetime_utc(time: SystemTime) -> DateTime<Utc> {
     DateTime::from(time)
 }
+
+/// Parses a date (`2024-05-31`, midnight UTC) or an RFC 3339 timestamp (`2024-05-31T12:00:00Z`)
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("invalid date (expected YYYY-MM-DD or RFC 3339): {value}"))
+}
+
+/// Parses a time window: a number with unit `h` (hours), `d` (days) or `w` (weeks), e.g. `14d`
+pub fn parse_duration(value: &str) -> Result<TimeDelta, String> {
+    let invalid = || format!("invalid duration (expected e.g. 12h, 14d or 2w): {value}");
+
+    let unit = value.chars().last().ok_or_else(invalid)?;
+    let amount = value[..value.len() - unit.len_utf8()]
+        .parse::<i64>()
+        .map_err(|_| invalid())?;
+
+    match unit {
+        'h' => TimeDelta::try_hours(amount),
+        'd' => TimeDelta::try_days(amount),
+        'w' => TimeDelta::try_weeks(amount),
+        _ => None,
+    }
+    .filter(|duration| *duration > TimeDelta::zero())
+    .ok_or_else(invalid)
+}
+
+/// Formats the date in local time for terminal output
+pub fn to_local_string(date: &DateTime<Utc>) -> String {
+    date.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2024-05-31").unwrap().to_rfc3339(),
+            "2024-05-31T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_date("2024-05-31T14:00:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-05-31T12:00:00+00:00"
+        );
+        assert!(parse_date("31.05.2024").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("12h").unwrap(), TimeDelta::hours(12));
+        assert_eq!(parse_duration("14d").unwrap(), TimeDelta::days(14));
+        assert_eq!(parse_duration("2w").unwrap(), TimeDelta::weeks(2));
+        assert!(parse_duration("0d").is_err());
+        assert!(parse_duration("14").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("").is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod dates;
 pub mod strings;
+pub mod table;
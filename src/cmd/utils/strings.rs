@@ -11,10 +11,9 @@ pub fn format_error_message(message: &str) -> String {
 #[allow(
     clippy::cast_possible_truncation,
     clippy::cast_sign_loss,
-    clippy::cast_precision_loss,
-    unused
+    clippy::cast_precision_loss
 )]
-fn to_readable_size(size: u64) -> String {
+pub fn to_readable_size(size: u64) -> String {
     let units = ["B", "KB", "MB", "GB", "TB", "PB"];
 
     if size == 0 {
@@ -35,6 +34,45 @@ fn to_readable_size(size: u64) -> String {
     format!("{res:.0} {}", units[exp as usize])
 }
 
+/// Parses a size with an optional binary unit, e.g. `500M`, `10 GB` or `1024` (bytes)
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid size: {value}"))?;
+
+    let exp = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" => 1,
+        "M" | "MB" => 2,
+        "G" | "GB" => 3,
+        "T" | "TB" => 4,
+        _ => {
+            return Err(format!(
+                "Invalid size unit (use B, KB, MB, GB or TB): {value}"
+            ))
+        }
+    };
+
+    number
+        .checked_mul(1024u64.pow(exp))
+        .ok_or_else(|| format!("Size too large: {value}"))
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+pub fn to_csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,4 +128,21 @@ mod tests {
         let size = 12_500_000_000_000_000_u64;
         assert_eq!("11 PB", to_readable_size(size));
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(Ok(0), parse_size("0"));
+        assert_eq!(Ok(1024), parse_size("1024"));
+        assert_eq!(Ok(500 * 1024 * 1024), parse_size("500M"));
+        assert_eq!(Ok(10 * 1024 * 1024 * 1024), parse_size("10 GB"));
+        assert!(parse_size("10 XB").is_err());
+        assert!(parse_size("GB").is_err());
+    }
+
+    #[test]
+    fn test_to_csv_field() {
+        assert_eq!("report.pdf", to_csv_field("report.pdf"));
+        assert_eq!("\"a, b\"", to_csv_field("a, b"));
+        assert_eq!("\"say \"\"hi\"\"\"", to_csv_field("say \"hi\""));
+    }
 }
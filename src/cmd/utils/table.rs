@@ -0,0 +1,76 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Formats the header and rows as columns separated by two spaces.
+/// Columns are padded to their widest cell - except for the last one.
+pub fn format_table<const N: usize>(
+    header: [&str; N],
+    align: [Align; N],
+    rows: &[[String; N]],
+) -> Vec<String> {
+    let header = header.map(String::from);
+
+    let widths = std::iter::once(&header)
+        .chain(rows)
+        .fold([0; N], |mut widths, row| {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+            widths
+        });
+
+    std::iter::once(&header)
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter().zip(align))
+                .enumerate()
+                .map(|(column, (cell, (&width, align)))| match align {
+                    _ if column == N - 1 => cell.clone(),
+                    Align::Left => format!("{cell:<width$}"),
+                    Align::Right => format!("{cell:>width$}"),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let rows = [
+            [
+                "a.pdf".to_string(),
+                "1 KB".to_string(),
+                "/Clients".to_string(),
+            ],
+            [
+                "report.pdf".to_string(),
+                "20 MB".to_string(),
+                "/".to_string(),
+            ],
+        ];
+
+        let lines = format_table(
+            ["NAME", "SIZE", "ROOM"],
+            [Align::Left, Align::Right, Align::Left],
+            &rows,
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                "NAME         SIZE  ROOM",
+                "a.pdf        1 KB  /Clients",
+                "report.pdf  20 MB  /",
+            ]
+        );
+    }
+}
@@ -1,13 +1,24 @@
 use clap::Parser;
 use cmd::{
-    config::handle_config_cmd,
-    models::{Syncoon, SyncoonCommand},
+    config::{handle_config_cmd, handle_logout_cmd},
+    models::{
+        AccountOptions, CredentialOptions, ExpirationChange, ExpiringOptions, HistoryOptions,
+        NotifyOptions, RecoverOptions, RoomConfigOptions, RoomSettings, RoomsCommand, RunOptions,
+        ShareOptions, Syncoon, SyncoonCommand, WatchOptions,
+    },
     print_version,
-    remover::handle_remove_expiration,
+    remover::{
+        handle_baseline, handle_configure, handle_diff, handle_expiring, handle_history,
+        handle_recover, handle_remove_expiration, handle_shares, handle_users, handle_watch,
+    },
 };
 use console::Term;
 
-use cmd::{config::logs::init_logging, handle_error, models::PasswordAuth};
+use cmd::{
+    config::logs::{init_logging, shutdown_telemetry},
+    handle_error,
+    models::{LogOptions, PasswordAuth},
+};
 
 mod cmd;
 mod env_vars;
@@ -15,27 +26,256 @@ mod env_vars;
 #[tokio::main]
 async fn main() {
     let opt = Syncoon::parse();
+    let debug = opt.debug;
 
     let term = Term::stdout();
     let err_term = Term::stderr();
 
-    init_logging(&err_term, opt.debug, opt.log_file_path);
+    init_logging(
+        &err_term,
+        LogOptions {
+            debug: opt.debug,
+            stderr: opt.log_file_out,
+            file: opt.log_file_path,
+            format: opt.log_format,
+            rotation: opt.log_rotation,
+            max_size: opt.log_max_size * 1024 * 1024,
+            retention: opt.log_retention,
+            #[cfg(feature = "otel")]
+            otlp_endpoint: opt.otlp_endpoint,
+        },
+    );
 
     let password_auth = match (opt.username, opt.password) {
         (Some(username), Some(password)) => Some(PasswordAuth(username, password)),
         _ => None,
     };
 
+    let credentials = CredentialOptions {
+        store: opt.credential_store,
+        file: opt.credential_file,
+        passphrase: opt.credential_passphrase,
+    };
+
     let res = match opt.cmd {
         SyncoonCommand::Run {
             dracoon,
-            data_room_id,
-        } => handle_remove_expiration(term, dracoon, data_room_id, password_auth).await,
-        SyncoonCommand::Config { cmd } => handle_config_cmd(cmd, term).await,
+            data_room_ids,
+            all_managed,
+            skip_unmanaged,
+            yes,
+            dry_run,
+            stream,
+        } => {
+            let opts = RunOptions {
+                data_room_ids,
+                all_managed,
+                skip_unmanaged,
+                yes,
+                dry_run,
+                stream,
+            };
+
+            let notify = NotifyOptions {
+                config: opt.notify_config,
+                profile: opt.notify_profile,
+            };
+
+            handle_remove_expiration(term, dracoon, opts, password_auth, credentials, notify).await
+        }
+        SyncoonCommand::Watch {
+            dracoon,
+            rooms,
+            all_managed,
+            interval,
+        } => {
+            let opts = WatchOptions {
+                rooms,
+                all_managed,
+                interval,
+            };
+
+            handle_watch(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::Baseline {
+            dracoon,
+            rooms,
+            all_managed,
+            output,
+        } => {
+            handle_baseline(
+                term,
+                dracoon,
+                rooms,
+                all_managed,
+                &output,
+                password_auth,
+                credentials,
+            )
+            .await
+        }
+        SyncoonCommand::Diff {
+            dracoon,
+            baseline,
+            format,
+        } => handle_diff(term, dracoon, &baseline, format, password_auth, credentials).await,
+        SyncoonCommand::Recover {
+            dracoon,
+            rooms,
+            all_managed,
+            deleted_after,
+            deleted_before,
+            deleted_by,
+            all_versions,
+            on_conflict,
+            yes,
+            dry_run,
+            format,
+        } => {
+            let opts = RecoverOptions {
+                rooms,
+                all_managed,
+                deleted_after,
+                deleted_before,
+                deleted_by,
+                all_versions,
+                on_conflict,
+                yes,
+                dry_run,
+                format,
+            };
+
+            handle_recover(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::Expiring {
+            dracoon,
+            rooms,
+            all_managed,
+            within,
+            format,
+        } => {
+            let opts = ExpiringOptions {
+                rooms,
+                all_managed,
+                within,
+                format,
+            };
+
+            handle_expiring(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::Shares {
+            dracoon,
+            rooms,
+            all_managed,
+            kind,
+            expires_before,
+            created_by,
+            remove,
+            extend_to,
+            extend_by,
+            yes,
+            dry_run,
+            format,
+        } => {
+            let opts = ShareOptions {
+                rooms,
+                all_managed,
+                kind,
+                expires_before,
+                created_by,
+                change: ExpirationChange::new(remove, extend_to, extend_by),
+                yes,
+                dry_run,
+                format,
+            };
+
+            handle_shares(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::Users {
+            dracoon,
+            rooms,
+            kind,
+            expires_before,
+            remove,
+            extend_to,
+            extend_by,
+            yes,
+            dry_run,
+            format,
+        } => {
+            let opts = AccountOptions {
+                rooms,
+                kind,
+                expires_before,
+                change: ExpirationChange::new(remove, extend_to, extend_by),
+                yes,
+                dry_run,
+                format,
+            };
+
+            handle_users(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::Rooms {
+            cmd:
+                RoomsCommand::Configure {
+                    dracoon,
+                    rooms,
+                    all_managed,
+                    recycle_bin_retention,
+                    quota,
+                    inherit_permissions,
+                    skip_unmanaged,
+                    yes,
+                    dry_run,
+                    format,
+                },
+        } => {
+            let opts = RoomConfigOptions {
+                rooms,
+                all_managed,
+                settings: RoomSettings {
+                    recycle_bin_retention,
+                    quota,
+                    inherit_permissions,
+                },
+                skip_unmanaged,
+                yes,
+                dry_run,
+                format,
+            };
+
+            handle_configure(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::History {
+            dracoon,
+            rooms,
+            all_managed,
+            from,
+            to,
+            operations,
+            format,
+        } => {
+            let opts = HistoryOptions {
+                rooms,
+                all_managed,
+                from,
+                to,
+                operations,
+                format,
+            };
+
+            handle_history(term, dracoon, opts, password_auth, credentials).await
+        }
+        SyncoonCommand::Logout { targets, all } => {
+            handle_logout_cmd(targets, all, term, credentials).await
+        }
+        SyncoonCommand::Config { cmd } => handle_config_cmd(cmd, term, credentials).await,
         SyncoonCommand::Version => print_version(&term),
     };
 
     if let Err(e) = res {
-        handle_error(&err_term, &e);
+        handle_error(&err_term, &e, debug);
     }
+
+    shutdown_telemetry();
 }